@@ -1 +1,5 @@
+pub mod ordering;
 pub mod policy;
+pub mod recorder;
+pub mod search;
+pub mod tt;
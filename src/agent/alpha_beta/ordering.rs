@@ -0,0 +1,210 @@
+use std::fmt::Display;
+
+use crate::game::{
+    board::{Board, BOARDSIZE},
+    r#move::Move,
+};
+
+const NUM_SQUARES: usize = BOARDSIZE * BOARDSIZE;
+
+/// Order of the move classes. Higher classes are searched first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveClass {
+    Quiet,
+    Killer,
+    KingToCorner,
+    Capture,
+    KingEscape,
+    TtMove,
+}
+
+/// How often moves of each class caused a beta cutoff
+pub struct CutoffStats {
+    nodes: usize,
+    quiescence_nodes: usize,
+    cutoffs: usize,
+    first_move_cutoffs: usize,
+    class_cutoffs: [usize; 6],
+}
+
+/// Move ordering using the transposition table move, tactical moves,
+/// killer moves per ply and the history heuristic.
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<u64>,
+    stats: CutoffStats,
+}
+
+impl CutoffStats {
+    pub fn new() -> CutoffStats {
+        CutoffStats {
+            nodes: 0,
+            quiescence_nodes: 0,
+            cutoffs: 0,
+            first_move_cutoffs: 0,
+            class_cutoffs: [0; 6],
+        }
+    }
+}
+
+impl Display for CutoffStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cutoffs = self.cutoffs.max(1) as f64;
+        writeln!(
+            f,
+            "searched nodes: {}, quiescence nodes: {}, cutoffs: {}, first move cutoffs: {:.1}%",
+            self.nodes,
+            self.quiescence_nodes,
+            self.cutoffs,
+            100.0 * self.first_move_cutoffs as f64 / cutoffs
+        )?;
+        write!(
+            f,
+            "cutoffs by tt move: {}, king escape: {}, capture: {}, king to corner: {}, killer: {}, quiet: {}",
+            self.class_cutoffs[MoveClass::TtMove as usize],
+            self.class_cutoffs[MoveClass::KingEscape as usize],
+            self.class_cutoffs[MoveClass::Capture as usize],
+            self.class_cutoffs[MoveClass::KingToCorner as usize],
+            self.class_cutoffs[MoveClass::Killer as usize],
+            self.class_cutoffs[MoveClass::Quiet as usize],
+        )
+    }
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![],
+            history: vec![0; NUM_SQUARES * NUM_SQUARES],
+            stats: CutoffStats::new(),
+        }
+    }
+
+    /// Prepares the ordering for a new search.
+    /// Killers and statistics are cleared and the history is aged.
+    pub fn new_search(&mut self) {
+        self.killers.clear();
+        self.history.iter_mut().for_each(|score| *score /= 2);
+        self.stats = CutoffStats::new();
+    }
+
+    /// Get the cutoff statistics of the current search
+    pub fn get_stats(&self) -> &CutoffStats {
+        &self.stats
+    }
+
+    /// Sorts the moves by class and within a class by history score.
+    /// Moves with equal keys keep their order, so shuffled input stays random.
+    pub fn order_moves(
+        &self,
+        board: &Board,
+        moves: Vec<Move>,
+        tt_move: &Option<Move>,
+        ply: usize,
+    ) -> Vec<(Move, MoveClass)> {
+        let mut scored: Vec<(Move, MoveClass, u64)> = moves
+            .into_iter()
+            .map(|mov| {
+                let (class, score) = self.classify(board, &mov, tt_move, ply);
+                (mov, class, score)
+            })
+            .collect();
+
+        scored.sort_by(|(_, class_a, score_a), (_, class_b, score_b)| {
+            (class_b, score_b).cmp(&(class_a, score_a))
+        });
+
+        scored
+            .into_iter()
+            .map(|(mov, class, _)| (mov, class))
+            .collect()
+    }
+
+    /// Counts a searched node
+    pub fn record_node(&mut self) {
+        self.stats.nodes += 1;
+    }
+
+    /// Counts a node of the quiescence search
+    pub fn record_quiescence_node(&mut self) {
+        self.stats.quiescence_nodes += 1;
+    }
+
+    /// Updates killers, history and statistics after a beta cutoff.
+    /// Only quiet moves become killers and gain history.
+    pub fn record_cutoff(
+        &mut self,
+        mov: &Move,
+        class: MoveClass,
+        move_idx: usize,
+        depth: usize,
+        ply: usize,
+    ) {
+        self.stats.cutoffs += 1;
+        self.stats.class_cutoffs[class as usize] += 1;
+        if move_idx == 0 {
+            self.stats.first_move_cutoffs += 1;
+        }
+
+        if matches!(class, MoveClass::Quiet | MoveClass::Killer) {
+            if self.killers.len() <= ply {
+                self.killers.resize(ply + 1, [None, None]);
+            }
+            if self.killers[ply][0].as_ref() != Some(mov) {
+                self.killers[ply][1] = self.killers[ply][0].take();
+                self.killers[ply][0] = Some(mov.clone());
+            }
+
+            self.history[history_idx(mov)] += (depth * depth) as u64;
+        }
+    }
+
+    /// returns the class of the move and its score within the class
+    fn classify(
+        &self,
+        board: &Board,
+        mov: &Move,
+        tt_move: &Option<Move>,
+        ply: usize,
+    ) -> (MoveClass, u64) {
+        if tt_move.as_ref().is_some_and(|tt_mov| tt_mov == mov) {
+            return (MoveClass::TtMove, 0);
+        }
+
+        let start_dist = mov.get_start_pos().min_dist_to_corner();
+        let end_dist = mov.get_end_pos().min_dist_to_corner();
+        let is_king_move = board.is_king_move(mov);
+
+        if is_king_move && end_dist == 0 {
+            return (MoveClass::KingEscape, 0);
+        }
+
+        let captures = board.num_captures(mov);
+        if captures > 0 {
+            return (MoveClass::Capture, captures as u64);
+        }
+
+        if is_king_move && end_dist < start_dist {
+            return (MoveClass::KingToCorner, (start_dist - end_dist) as u64);
+        }
+
+        let history = self.history[history_idx(mov)];
+        if self.is_killer(mov, ply) {
+            (MoveClass::Killer, history)
+        } else {
+            (MoveClass::Quiet, history)
+        }
+    }
+
+    /// returns true if the move is a killer move at the given ply
+    fn is_killer(&self, mov: &Move, ply: usize) -> bool {
+        self.killers
+            .get(ply)
+            .is_some_and(|killers| killers.iter().any(|killer| killer.as_ref() == Some(mov)))
+    }
+}
+
+/// returns the index of the move in the history table
+fn history_idx(mov: &Move) -> usize {
+    mov.get_start_pos().get_num() * NUM_SQUARES + mov.get_end_pos().get_num()
+}
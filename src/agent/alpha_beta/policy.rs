@@ -1,135 +1,160 @@
-use core::f64;
-use std::time;
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread, time,
+};
 
 use crate::{
-    agent::{Bot, BotInit},
-    eval::Eval,
-    game::{
-        board::Board,
-        piece::{Piece, PieceColor},
-        r#move::Move,
+    agent::{
+        info::{InfoCallback, SearchInfo},
+        limits::SearchLimits,
+        time_manager::TimeManager,
+        trace::SearchTrace,
+        Bot, BotInit,
     },
+    eval::Eval,
+    game::{board::Board, r#move::Move},
+};
+
+use super::{
+    ordering::MoveOrdering,
+    search::{Searcher, SharedState},
+    tt::TranspositionTable,
 };
 
-use rand::{prelude::SliceRandom, thread_rng};
+pub struct AlphaBetaParams {
+    pub(crate) max_depth: usize,
+    pub(crate) tt_size_mb: usize,
+    pub(crate) quiescence_depth: usize,
+    pub(crate) use_pvs: bool,
+    pub(crate) use_aspiration: bool,
+    pub(crate) use_null_move: bool,
+    pub(crate) use_lmr: bool,
+    pub(crate) num_threads: usize,
+    /// plies of the search tree that are recorded for export, None to record nothing
+    pub(crate) trace_depth: Option<usize>,
+}
 
 pub struct AlphaBetaBot<T: Eval> {
     board: Board,
     eval_fn: T,
-    max_depth: usize,
-    best_move: Option<Move>,
+    params: AlphaBetaParams,
+    nodes: usize,
+    info: Option<SearchInfo>,
+    info_callback: Option<InfoCallback>,
+    /// tree of the last completed iteration of the main thread
+    trace: Option<SearchTrace>,
+    tt: TranspositionTable,
+    /// one move ordering per thread, the first one belongs to the main thread
+    orderings: Vec<MoveOrdering>,
 }
 
-impl<T: Eval> AlphaBetaBot<T> {
-    pub fn alpha_beta(
-        &mut self,
-        board: Board,
-        depth: usize,
-        mut alpha: f64,
-        beta: f64,
-        max_player: bool,
-        dist_from_root: usize,
-        first_move: Option<Move>,
-    ) -> f64 {
-        if depth == 0 || board.is_game_over() {
-            let factor = if max_player { 1.0 } else { -1.0 };
-            if board.is_game_over() {
-                let addition: f64 = match board.who_won() {
-                    crate::game::board::GameState::Undecided => 0,
-                    crate::game::board::GameState::WinAttacker => -(dist_from_root as i32),
-                    crate::game::board::GameState::WinDefender => dist_from_root as i32,
-                    crate::game::board::GameState::Draw => 0,
-                } as f64;
-                return factor * (self.eval_fn.get_eval(&board) + addition);
-            } else {
-                return factor * self.eval_fn.get_eval(&board);
-            }
-        }
-
-        let mut legal_moves = board.get_legal_moves();
-        legal_moves.shuffle(&mut thread_rng());
-        if let Some(fm) = first_move.clone() {
-            legal_moves.insert(0, fm);
-        }
-
-        let mut value = f64::NEG_INFINITY;
-
-        for (idx, mov) in legal_moves.iter().enumerate() {
-            if idx != 0 && first_move.as_ref().is_some_and(|fm| *fm == *mov) {
-                continue;
-            }
-
-            let mut child = board.clone();
-            child.make_move_captured_positions(mov);
-
-            let eval = -self.alpha_beta(
-                child,
-                depth - 1,
-                -beta,
-                -alpha,
-                !max_player,
-                dist_from_root + 1,
-                None,
-            );
-
-            if eval > value && dist_from_root == 0 {
-                self.best_move = Some(mov.clone());
-            }
-
-            value = value.max(eval);
-            alpha = alpha.max(value);
-
-            if alpha >= beta {
-                break;
-            }
+impl AlphaBetaParams {
+    /// Create the default parameters for the given search depth.
+    /// All search enhancements are enabled, the search is single-threaded and no tree is recorded.
+    pub fn new(max_depth: usize) -> AlphaBetaParams {
+        AlphaBetaParams {
+            max_depth,
+            tt_size_mb: 16,
+            quiescence_depth: 8,
+            use_pvs: true,
+            use_aspiration: true,
+            use_null_move: true,
+            use_lmr: true,
+            num_threads: 1,
+            trace_depth: None,
         }
-
-        value
     }
 }
 
 impl<T: Eval> BotInit for AlphaBetaBot<T> {
     type Ev = T;
-    type Params = usize;
+    type Params = AlphaBetaParams;
 
     fn new(bot_params: Self::Params, eval_fn: Self::Ev) -> Self {
+        let num_threads = bot_params.num_threads.max(1);
         AlphaBetaBot {
             board: Board::new(),
             eval_fn,
-            max_depth: bot_params,
-            best_move: None,
+            nodes: 0,
+            info: None,
+            info_callback: None,
+            trace: None,
+            tt: TranspositionTable::new(bot_params.tt_size_mb),
+            orderings: (0..num_threads).map(|_| MoveOrdering::new()).collect(),
+            params: bot_params,
         }
     }
 }
 
-impl<T: Eval> Bot for AlphaBetaBot<T> {
-    fn get_next_move(&mut self, board: &Board, time: u128) -> Option<Move> {
-        let start_time: time::Instant = time::Instant::now();
-
+impl<T: Eval + Send + Sync> Bot for AlphaBetaBot<T> {
+    /// Searches with Lazy SMP: helper threads search the same position and only
+    /// share their results through the transposition table.
+    /// The move of the main thread is played.
+    fn get_next_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
         self.reset(board);
-
-        let black_gaming =
-            Piece::Pawn(self.board.get_player().clone()).is_color(&PieceColor::Attacker);
-
-        self.best_move = None;
-
-        for i in 1..=self.max_depth {
-            self.alpha_beta(
-                board.clone(),
-                i,
-                f64::NEG_INFINITY + 1.0,
-                f64::INFINITY - 1.0,
-                black_gaming,
-                0,
-                self.best_move.clone(),
-            );
-
-            if start_time.elapsed().as_millis() > time {
-                break;
+        self.info = None;
+
+        let (mut time_manager, planned_limits) = TimeManager::new(limits, board);
+        let stop = AtomicBool::new(false);
+        let nodes = AtomicUsize::new(0);
+        let shared = SharedState {
+            eval_fn: &self.eval_fn,
+            params: &self.params,
+            tt: &self.tt,
+            stop: &stop,
+            nodes: &nodes,
+            start_time: time::Instant::now(),
+            limits: &planned_limits,
+        };
+
+        self.orderings.iter_mut().for_each(MoveOrdering::new_search);
+        let (main_ordering, helper_orderings) = self.orderings.split_first_mut().unwrap();
+        let info_callback = &mut self.info_callback;
+        let mut info = None;
+
+        let (best_move, trace) = thread::scope(|scope| {
+            for (idx, ordering) in helper_orderings.iter_mut().enumerate() {
+                let mut helper = Searcher::new(board, shared, ordering);
+                // every second helper skips the first depth, so the threads search different depths
+                let first_depth = 1 + (idx + 1) % 2;
+                scope.spawn(move || {
+                    limits.seed_rng(idx + 1);
+                    helper.iterative_deepening(first_depth, |_, _, _| true)
+                });
             }
-        }
 
-        self.best_move.clone()
+            limits.seed_rng(0);
+            let mut searcher = Searcher::new(board, shared, main_ordering);
+            if let Some(trace_depth) = self.params.trace_depth {
+                searcher.record_trace(trace_depth);
+            }
+            let best_move = searcher.iterative_deepening(1, |searcher, depth, score| {
+                let iteration_info = SearchInfo {
+                    depth,
+                    score,
+                    pv: searcher.principal_variation(depth),
+                    nodes: searcher.get_total_nodes(),
+                    time_ms: searcher.get_elapsed_ms(),
+                };
+                if let Some(callback) = info_callback.as_mut() {
+                    callback(&iteration_info);
+                }
+                let done = time_manager.should_stop(iteration_info.pv.first());
+                info = Some(iteration_info);
+                !done
+            });
+
+            stop.store(true, Ordering::Relaxed);
+            (best_move, searcher.take_trace())
+        });
+
+        // the search ends at the max depth, but an infinite search still waits to be stopped
+        limits.wait_for_stop();
+
+        self.nodes = nodes.load(Ordering::Relaxed);
+        self.info = info;
+        self.trace = trace;
+        best_move.or_else(|| board.get_random_move())
     }
 
     fn reset(&mut self, board: &Board) {
@@ -137,10 +162,56 @@ impl<T: Eval> Bot for AlphaBetaBot<T> {
     }
 
     fn num_nodes(&self) -> usize {
-        0
+        self.nodes
     }
 
     fn get_name(&self) -> String {
         "AlphaBeta".to_string()
     }
+
+    fn get_search_stats(&self) -> Option<String> {
+        Some(self.orderings[0].get_stats().to_string())
+    }
+
+    fn set_info_callback(&mut self, callback: InfoCallback) {
+        self.info_callback = Some(callback);
+    }
+
+    fn get_search_info(&self) -> Option<SearchInfo> {
+        self.info.clone()
+    }
+
+    fn get_search_trace(&self, max_depth: usize, min_visits: u32) -> Option<SearchTrace> {
+        self.trace
+            .as_ref()
+            .map(|trace| trace.limited(max_depth, min_visits))
+    }
+
+    /// Searches the position after the reply the bot expects to its own move silently,
+    /// or the position after the own move if it expects none. The results stay in the
+    /// transposition table, where the entries of a wrongly guessed position are replaced over time.
+    /// The information and the tree of the last real search are kept.
+    fn ponder(&mut self, board: &Board, limits: &SearchLimits) {
+        let last_board = self.board.clone();
+        let info = self.info.take();
+        let trace = self.trace.take();
+
+        let mut ponder_board = board.clone();
+        if let Some([mov, reply, ..]) = info.as_ref().map(|info| info.pv.as_slice()) {
+            let mut after_move = last_board.clone();
+            after_move.make_move_captured_positions(mov);
+            if after_move == *board && board.is_valid_move(reply, &board.get_player()) {
+                ponder_board.make_move_captured_positions(reply);
+            }
+        }
+
+        if !ponder_board.is_game_over() {
+            let callback = self.info_callback.take();
+            self.get_next_move(&ponder_board, limits);
+            self.info_callback = callback;
+        }
+        self.board = last_board;
+        self.info = info;
+        self.trace = trace;
+    }
 }
@@ -0,0 +1,139 @@
+use crate::{
+    agent::trace::{SearchTrace, TraceBound, TraceNode},
+    game::r#move::Move,
+};
+
+/// Records the nodes of the alpha-beta search down to a depth,
+/// so the tree of the last completed iteration can be exported
+pub struct TraceRecorder {
+    max_depth: usize,
+    current: Option<SearchTrace>,
+    completed: Option<SearchTrace>,
+    /// ids of the recorded nodes on the current path, one per ply
+    path: Vec<usize>,
+    /// the move that leads to the node searched next at every ply
+    moves: Vec<Option<Move>>,
+}
+
+impl TraceRecorder {
+    /// Create a recorder for the plies up to the given depth
+    pub fn new(max_depth: usize) -> TraceRecorder {
+        TraceRecorder {
+            max_depth,
+            current: None,
+            completed: None,
+            path: vec![],
+            moves: vec![None; max_depth + 1],
+        }
+    }
+
+    /// Sets the move that leads to the next node searched at the ply, None for a null move
+    pub fn set_move(&mut self, ply: usize, mov: Option<&Move>) {
+        if ply <= self.max_depth {
+            self.moves[ply] = mov.cloned();
+        }
+    }
+
+    /// Records a node that is searched with the given window.
+    /// A node at ply 0 starts a new tree, a re-search of a move reuses the node of the move.
+    pub fn enter(&mut self, ply: usize, alpha: f64, beta: f64) {
+        if ply > self.max_depth {
+            return;
+        }
+        let mut node = TraceNode::new(if ply == 0 {
+            None
+        } else {
+            self.moves[ply].clone()
+        });
+        node.window = Some((alpha, beta));
+
+        if ply == 0 {
+            self.current = Some(SearchTrace::new(node));
+            self.path = vec![0];
+            return;
+        }
+
+        self.path.truncate(ply);
+        let Some(trace) = self.current.as_mut() else {
+            return;
+        };
+        if self.path.len() == ply {
+            let parent = self.path[ply - 1];
+            let id = match trace.find_child(parent, &node.mov) {
+                Some(id) => {
+                    let child = trace.get_node_mut(id);
+                    child.window = node.window;
+                    child.pruned = false;
+                    id
+                }
+                None => trace.add_child(parent, node),
+            };
+            self.path.push(id);
+        }
+    }
+
+    /// Replaces the window of the node at the ply by the one it is actually searched with,
+    /// after the transposition table narrowed it
+    pub fn set_window(&mut self, ply: usize, alpha: f64, beta: f64) {
+        if ply > self.max_depth {
+            return;
+        }
+        let (Some(trace), Some(&id)) = (self.current.as_mut(), self.path.get(ply)) else {
+            return;
+        };
+        trace.get_node_mut(id).window = Some((alpha, beta));
+    }
+
+    /// Stores the score of the node at the ply and whether it failed low or high
+    pub fn leave(&mut self, ply: usize, score: f64) {
+        if ply > self.max_depth {
+            return;
+        }
+        self.path.truncate(ply + 1);
+        let (Some(trace), Some(&id)) = (self.current.as_mut(), self.path.get(ply)) else {
+            return;
+        };
+
+        let node = trace.get_node_mut(id);
+        let (alpha, beta) = node.window.unwrap();
+        node.score = Some(score);
+        node.bound = Some(if score <= alpha {
+            TraceBound::Upper
+        } else if score >= beta {
+            TraceBound::Lower
+        } else {
+            TraceBound::Exact
+        });
+    }
+
+    /// Records the moves of the node at the ply that were skipped by a cutoff
+    pub fn add_pruned<'m>(&mut self, ply: usize, moves: impl Iterator<Item = &'m Move>) {
+        if ply >= self.max_depth {
+            return;
+        }
+        let (Some(trace), Some(&id)) = (self.current.as_mut(), self.path.get(ply)) else {
+            return;
+        };
+
+        for mov in moves {
+            let mov = Some(mov.clone());
+            // a move searched before a re-search is not pruned
+            if trace.find_child(id, &mov).is_some() {
+                continue;
+            }
+            let mut node = TraceNode::new(mov);
+            node.pruned = true;
+            trace.add_child(id, node);
+        }
+    }
+
+    /// Keeps the tree of the iteration that has just been completed
+    pub fn complete(&mut self) {
+        self.completed = self.current.take();
+    }
+
+    /// Returns the tree of the last completed iteration
+    pub fn take_completed(&mut self) -> Option<SearchTrace> {
+        self.completed.take()
+    }
+}
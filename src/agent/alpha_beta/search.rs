@@ -0,0 +1,611 @@
+use core::f64;
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time,
+};
+
+use crate::{
+    agent::{limits::SearchLimits, trace::SearchTrace},
+    eval::Eval,
+    game::{
+        board::{Board, GameState},
+        piece::PieceColor,
+        r#move::Move,
+    },
+    utils::rng,
+};
+
+use rand::prelude::SliceRandom;
+
+use super::{
+    ordering::{MoveClass, MoveOrdering},
+    policy::AlphaBetaParams,
+    recorder::TraceRecorder,
+    tt::{Bound, TranspositionTable},
+};
+
+/// width of the window used to test whether a move is better than alpha
+const NULL_WINDOW: f64 = 1e-3;
+/// initial half width of the aspiration window around the previous score
+const ASPIRATION_WINDOW: f64 = 2.0;
+/// depth reduction of the null move search
+const NULL_MOVE_REDUCTION: usize = 2;
+/// number of moves searched at full depth before late moves are reduced
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+/// number of nodes between two checks of the time limit
+const TIME_CHECK_INTERVAL: usize = 1024;
+/// score of a won game at the root, every ply to the end of the game costs one point
+pub const WIN_SCORE: f64 = 1000.0;
+
+/// State shared by all threads searching the same position
+pub struct SharedState<'a, T: Eval> {
+    pub eval_fn: &'a T,
+    pub params: &'a AlphaBetaParams,
+    pub tt: &'a TranspositionTable,
+    /// set as soon as one thread reaches a limit or the main thread is done
+    pub stop: &'a AtomicBool,
+    /// nodes visited by all threads, updated at every time check
+    pub nodes: &'a AtomicUsize,
+    pub start_time: time::Instant,
+    pub limits: &'a SearchLimits,
+}
+
+/// A single thread of the alpha-beta search.
+/// All searchers of a position share the transposition table.
+pub struct Searcher<'a, T: Eval> {
+    board: Board,
+    shared: SharedState<'a, T>,
+    ordering: &'a mut MoveOrdering,
+    nodes: usize,
+    aborted: bool,
+    best_move: Option<Move>,
+    pv_table: Vec<Vec<Move>>,
+    /// records the searched tree if it should be exported
+    recorder: Option<TraceRecorder>,
+}
+
+impl<T: Eval> Clone for SharedState<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Eval> Copy for SharedState<'_, T> {}
+
+impl<'a, T: Eval> Searcher<'a, T> {
+    pub fn new(
+        board: &Board,
+        shared: SharedState<'a, T>,
+        ordering: &'a mut MoveOrdering,
+    ) -> Searcher<'a, T> {
+        Searcher {
+            board: board.clone(),
+            shared,
+            ordering,
+            nodes: 0,
+            aborted: false,
+            best_move: None,
+            pv_table: vec![],
+            recorder: None,
+        }
+    }
+
+    /// Searches with increasing depth until a limit is reached.
+    /// Without a depth limit the max depth of the parameters is searched at most.
+    /// After each completed iteration `on_iteration` is called with the depth and the score,
+    /// it returns false to end the search early.
+    /// Returns the best move of the last completed iteration.
+    pub fn iterative_deepening(
+        &mut self,
+        first_depth: usize,
+        mut on_iteration: impl FnMut(&Searcher<'a, T>, usize, f64) -> bool,
+    ) -> Option<Move> {
+        let board = self.board.clone();
+        let mut completed_move = None;
+        let mut score = 0.0;
+
+        let max_depth = self
+            .shared
+            .limits
+            .depth
+            .unwrap_or(self.shared.params.max_depth);
+
+        for depth in first_depth..=max_depth {
+            score = if self.shared.params.use_aspiration && depth > first_depth {
+                self.aspiration_search(&board, depth, score)
+            } else {
+                self.alpha_beta(&board, depth, f64::NEG_INFINITY, f64::INFINITY, 0, true)
+            };
+
+            // an aborted iteration falls back to the move of the last completed one
+            if self.aborted {
+                break;
+            }
+            completed_move = self.best_move.clone();
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.complete();
+            }
+            if !on_iteration(self, depth, score)
+                || self.shared.limits.time_is_up(&self.shared.start_time)
+            {
+                break;
+            }
+        }
+
+        self.shared.nodes.fetch_add(self.nodes, Ordering::Relaxed);
+        self.nodes = 0;
+
+        // if not even the first iteration finished, the best move found so far is used
+        completed_move.or_else(|| self.best_move.clone())
+    }
+
+    /// Records the searched tree down to the given depth
+    pub fn record_trace(&mut self, max_depth: usize) {
+        self.recorder = Some(TraceRecorder::new(max_depth));
+    }
+
+    /// Returns the searched tree of the last completed iteration if it was recorded
+    pub fn take_trace(&mut self) -> Option<SearchTrace> {
+        self.recorder.as_mut()?.take_completed()
+    }
+
+    /// Get the number of nodes visited by all threads so far
+    pub fn get_total_nodes(&self) -> usize {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes
+    }
+
+    /// Get the elapsed time of the search in ms
+    pub fn get_elapsed_ms(&self) -> u128 {
+        self.shared.start_time.elapsed().as_millis()
+    }
+
+    /// Negamax search with alpha-beta pruning.
+    /// The returned score is from the view of the player to move.
+    /// `allow_null` is false directly after a null move, so two passes never follow each other.
+    pub fn alpha_beta(
+        &mut self,
+        board: &Board,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+        dist_from_root: usize,
+        allow_null: bool,
+    ) -> f64 {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.enter(dist_from_root, alpha, beta);
+        }
+        let value = self.search_node(board, depth, alpha, beta, dist_from_root, allow_null);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.leave(dist_from_root, value);
+        }
+        value
+    }
+
+    /// Searches a node for `alpha_beta`, which records it in the trace
+    fn search_node(
+        &mut self,
+        board: &Board,
+        depth: usize,
+        mut alpha: f64,
+        mut beta: f64,
+        dist_from_root: usize,
+        allow_null: bool,
+    ) -> f64 {
+        if depth == 0 {
+            return self.quiescence(
+                board,
+                alpha,
+                beta,
+                dist_from_root,
+                self.shared.params.quiescence_depth,
+            );
+        }
+
+        if self.count_node() {
+            return 0.0;
+        }
+        self.ordering.record_node();
+        self.clear_pv(dist_from_root);
+
+        if board.is_game_over() {
+            return self.terminal_score(board, dist_from_root);
+        }
+
+        // scout searches are NULL_WINDOW wide, every wider window belongs to a node on the principal variation
+        let is_pv_node = beta - alpha > 2.0 * NULL_WINDOW;
+        let hash = board.get_hash();
+        let mut tt_move = None;
+
+        if let Some(entry) = self.shared.tt.probe(hash) {
+            tt_move = entry.get_best_move().clone();
+
+            // the root always has to be searched to know the best move
+            if entry.get_depth() >= depth && dist_from_root > 0 {
+                let score = entry.get_score(dist_from_root);
+                match entry.get_bound() {
+                    Bound::Exact => return score,
+                    Bound::Lower => alpha = alpha.max(score),
+                    Bound::Upper => beta = beta.min(score),
+                }
+                if alpha >= beta {
+                    return score;
+                }
+            }
+        }
+        // the result is classified against the window that is actually searched
+        let alpha_orig = alpha;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.set_window(dist_from_root, alpha, beta);
+        }
+
+        let king_threatened = king_threatens_escape(board);
+
+        // if passing still fails high the position is good enough to cut off
+        if self.shared.params.use_null_move
+            && allow_null
+            && !is_pv_node
+            && dist_from_root > 0
+            && depth > NULL_MOVE_REDUCTION
+            && !king_threatened
+            && beta.is_finite()
+            && player_factor(board) * self.shared.eval_fn.get_eval(board) >= beta
+        {
+            let mut child = board.clone();
+            child.make_null_move();
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.set_move(dist_from_root + 1, None);
+            }
+
+            let eval = -self.alpha_beta(
+                &child,
+                depth - 1 - NULL_MOVE_REDUCTION,
+                -beta,
+                -beta + NULL_WINDOW,
+                dist_from_root + 1,
+                false,
+            );
+            if self.aborted {
+                return 0.0;
+            }
+            if eval >= beta {
+                return beta;
+            }
+        }
+
+        // moves are shuffled so moves the ordering can not distinguish are tried randomly
+        let mut legal_moves = board.get_legal_moves();
+        rng::with_rng(|rng| legal_moves.shuffle(rng));
+        let ordered_moves = self
+            .ordering
+            .order_moves(board, legal_moves, &tt_move, dist_from_root);
+
+        let mut value = f64::NEG_INFINITY;
+        let mut best_local_move = None;
+
+        for (idx, (mov, class)) in ordered_moves.iter().enumerate() {
+            let mut child = board.clone();
+            child.make_move_captured_positions(mov);
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.set_move(dist_from_root + 1, Some(mov));
+            }
+
+            let eval = if idx == 0 {
+                -self.alpha_beta(&child, depth - 1, -beta, -alpha, dist_from_root + 1, true)
+            } else {
+                let reduction = if self.shared.params.use_lmr
+                    && idx >= LMR_FULL_DEPTH_MOVES
+                    && depth >= 3
+                    && dist_from_root > 0
+                    && *class == MoveClass::Quiet
+                    && !king_threatened
+                {
+                    1
+                } else {
+                    0
+                };
+
+                // later moves are only tested against alpha and searched again if they beat it
+                let scout_beta = if self.shared.params.use_pvs {
+                    alpha + NULL_WINDOW
+                } else {
+                    beta
+                };
+
+                let mut eval = -self.alpha_beta(
+                    &child,
+                    depth - 1 - reduction,
+                    -scout_beta,
+                    -alpha,
+                    dist_from_root + 1,
+                    true,
+                );
+                if reduction > 0 && eval > alpha {
+                    eval = -self.alpha_beta(
+                        &child,
+                        depth - 1,
+                        -scout_beta,
+                        -alpha,
+                        dist_from_root + 1,
+                        true,
+                    );
+                }
+                if scout_beta < beta && eval > alpha && eval < beta {
+                    eval = -self.alpha_beta(
+                        &child,
+                        depth - 1,
+                        -beta,
+                        -alpha,
+                        dist_from_root + 1,
+                        true,
+                    );
+                }
+                eval
+            };
+
+            // the result of an aborted search is not usable
+            if self.aborted {
+                return 0.0;
+            }
+
+            if eval > value {
+                value = eval;
+                best_local_move = Some(mov.clone());
+                // a root that fails low keeps the best move until it is searched with a wider window
+                if eval > alpha {
+                    if dist_from_root == 0 {
+                        self.best_move = Some(mov.clone());
+                    }
+                    self.update_pv(dist_from_root, mov);
+                }
+            }
+
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                self.ordering
+                    .record_cutoff(mov, *class, idx, depth, dist_from_root);
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder.add_pruned(
+                        dist_from_root,
+                        ordered_moves[idx + 1..].iter().map(|(mov, _)| mov),
+                    );
+                }
+                break;
+            }
+        }
+
+        let bound = if value <= alpha_orig {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.shared
+            .tt
+            .store(hash, depth, bound, value, dist_from_root, best_local_move);
+
+        value
+    }
+
+    /// Searches captures and king escape threats until the position is quiet.
+    /// If the king threatens to escape the attacker can not stand pat and all
+    /// moves are searched, so blocking moves are found as well.
+    fn quiescence(
+        &mut self,
+        board: &Board,
+        mut alpha: f64,
+        beta: f64,
+        dist_from_root: usize,
+        q_depth: usize,
+    ) -> f64 {
+        if self.count_node() {
+            return 0.0;
+        }
+        self.ordering.record_quiescence_node();
+        self.clear_pv(dist_from_root);
+
+        if board.is_game_over() {
+            return self.terminal_score(board, dist_from_root);
+        }
+
+        let stand_pat = player_factor(board) * self.shared.eval_fn.get_eval(board);
+        if q_depth == 0 {
+            return stand_pat;
+        }
+
+        let king_threatened = king_threatens_escape(board);
+
+        let moves = if king_threatened {
+            board.get_legal_moves()
+        } else {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            tactical_moves(board)
+        };
+
+        let mut value = if king_threatened {
+            f64::NEG_INFINITY
+        } else {
+            stand_pat
+        };
+
+        for (mov, _) in self
+            .ordering
+            .order_moves(board, moves, &None, dist_from_root)
+        {
+            let mut child = board.clone();
+            child.make_move_captured_positions(&mov);
+
+            let eval = -self.quiescence(&child, -beta, -alpha, dist_from_root + 1, q_depth - 1);
+            if self.aborted {
+                return 0.0;
+            }
+
+            value = value.max(eval);
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        value
+    }
+
+    /// Searches the root with a window around the score of the previous iteration.
+    /// The window is widened on the failing side until the score lies inside it.
+    fn aspiration_search(&mut self, board: &Board, depth: usize, prev_score: f64) -> f64 {
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = prev_score - delta;
+        let mut beta = prev_score + delta;
+
+        loop {
+            let score = self.alpha_beta(board, depth, alpha, beta, 0, true);
+            if self.aborted || (score > alpha && score < beta) {
+                return score;
+            }
+
+            delta *= 2.0;
+            // after a few failures the window is opened completely
+            if delta > 8.0 * ASPIRATION_WINDOW {
+                delta = f64::INFINITY;
+            }
+
+            if score <= alpha {
+                alpha = prev_score - delta;
+            } else {
+                beta = prev_score + delta;
+            }
+        }
+    }
+
+    /// Clears the principal variation starting at the given ply
+    fn clear_pv(&mut self, ply: usize) {
+        if self.pv_table.len() <= ply + 1 {
+            self.pv_table.resize(ply + 2, vec![]);
+        }
+        self.pv_table[ply].clear();
+    }
+
+    /// Sets the principal variation at the ply to the move followed by the variation of the child
+    fn update_pv(&mut self, ply: usize, mov: &Move) {
+        let (current, rest) = self.pv_table.split_at_mut(ply + 1);
+        current[ply].clear();
+        current[ply].push(mov.clone());
+        current[ply].extend(rest[0].iter().cloned());
+    }
+
+    /// Returns the principal variation of the last iteration.
+    /// Lines cut short by transposition table hits are completed with the stored best moves.
+    pub fn principal_variation(&self, depth: usize) -> Vec<Move> {
+        let mut pv = self.pv_table.first().cloned().unwrap_or_default();
+        let mut board = self.board.clone();
+        for mov in pv.iter() {
+            board.make_move_captured_positions(mov);
+        }
+
+        while pv.len() < depth && !board.is_game_over() {
+            let Some(mov) = self
+                .shared
+                .tt
+                .probe(board.get_hash())
+                .and_then(|entry| entry.get_best_move().clone())
+                .filter(|mov| board.get_legal_moves().contains(mov))
+            else {
+                break;
+            };
+            board.make_move_captured_positions(&mov);
+            pv.push(mov);
+        }
+
+        pv
+    }
+
+    /// Counts a visited node and checks the node budget.
+    /// The time and the stop flags are only checked from time to time.
+    /// Returns true if the search has to be aborted.
+    // usize::is_multiple_of needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    fn count_node(&mut self) -> bool {
+        self.nodes += 1;
+
+        // the budget is checked at every node, so node limited searches with a seed are reproducible
+        if self
+            .shared
+            .limits
+            .nodes
+            .is_some_and(|max_nodes| self.get_total_nodes() > max_nodes)
+        {
+            self.abort();
+        }
+
+        if self.nodes % TIME_CHECK_INTERVAL == 0 {
+            self.shared.nodes.fetch_add(self.nodes, Ordering::Relaxed);
+            self.nodes = 0;
+
+            if self.shared.stop.load(Ordering::Relaxed)
+                || self.shared.limits.time_is_up(&self.shared.start_time)
+            {
+                self.abort();
+            }
+        }
+        self.aborted
+    }
+
+    /// Aborts the search of this thread and tells the other threads to stop
+    fn abort(&mut self) {
+        self.aborted = true;
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Score of a finished game from the view of the player to move.
+    /// Faster wins and slower losses are preferred.
+    fn terminal_score(&self, board: &Board, dist_from_root: usize) -> f64 {
+        let score = match board.who_won() {
+            GameState::WinAttacker => WIN_SCORE - dist_from_root as f64,
+            GameState::WinDefender => -WIN_SCORE + dist_from_root as f64,
+            GameState::Undecided | GameState::Draw => self.shared.eval_fn.get_eval(board),
+        };
+        player_factor(board) * score
+    }
+}
+
+/// returns 1 if the attacker is to move and -1 otherwise
+fn player_factor(board: &Board) -> f64 {
+    match board.get_player() {
+        PieceColor::Attacker => 1.0,
+        PieceColor::Defender => -1.0,
+    }
+}
+
+/// returns true if the attacker is to move and the king can reach a corner
+fn king_threatens_escape(board: &Board) -> bool {
+    board.get_player() == PieceColor::Attacker && !board.king_escape_moves().is_empty()
+}
+
+/// Returns the moves that are searched in the quiescence search:
+/// captures and king moves that reach a corner or threaten to reach one.
+fn tactical_moves(board: &Board) -> Vec<Move> {
+    board
+        .get_legal_moves()
+        .into_iter()
+        .filter(|mov| {
+            if board.num_captures(mov) > 0 {
+                return true;
+            }
+            if !board.is_king_move(mov) {
+                return false;
+            }
+            if mov.get_end_pos().is_corner() {
+                return true;
+            }
+
+            let mut child = board.clone();
+            child.make_move_captured_positions(mov);
+            !child.king_escape_moves().is_empty()
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::game::{position::Position, r#move::Move};
+
+use super::search::WIN_SCORE;
+
+/// scores at least this far from zero are wins or losses, they depend on the distance to the end of the game
+const WIN_THRESHOLD: f64 = WIN_SCORE - 500.0;
+
+/// How the stored score relates to the true value of the position
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone)]
+pub struct TtEntry {
+    depth: usize,
+    bound: Bound,
+    score: f64,
+    best_move: Option<Move>,
+}
+
+/// A slot of the table. The key is stored xor the data, so an entry that was
+/// torn by two threads writing at the same time does not verify and is ignored.
+struct TtSlot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed-size transposition table indexed by the zobrist hash of the board.
+/// It can be shared between threads without locking.
+pub struct TranspositionTable {
+    slots: Vec<TtSlot>,
+    mask: usize,
+}
+
+impl TtEntry {
+    /// Get the depth the entry was searched with
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the bound type of the score
+    pub fn get_bound(&self) -> Bound {
+        self.bound
+    }
+
+    /// Get the score from the view of the player to move.
+    /// Wins and losses are stored by their distance from the node and are returned by their
+    /// distance from the root of the search that probes the node at the given distance.
+    pub fn get_score(&self, dist_from_root: usize) -> f64 {
+        if self.score >= WIN_THRESHOLD {
+            self.score - dist_from_root as f64
+        } else if self.score <= -WIN_THRESHOLD {
+            self.score + dist_from_root as f64
+        } else {
+            self.score
+        }
+    }
+
+    /// Get the best move found in the position
+    pub fn get_best_move(&self) -> &Option<Move> {
+        &self.best_move
+    }
+
+    /// Packs the entry into 64 bits:
+    /// score as f32 (32 bits), depth (8), bound (2), move flag (1), start (7) and end (7) position
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let mov = match &self.best_move {
+            Some(mov) => {
+                1 << 14
+                    | (mov.get_start_pos().get_num() as u64) << 7
+                    | mov.get_end_pos().get_num() as u64
+            }
+            None => 0,
+        };
+
+        (self.score as f32).to_bits() as u64
+            | (self.depth.min(255) as u64) << 32
+            | bound << 40
+            | mov << 42
+    }
+
+    /// Unpacks an entry packed with `pack`
+    fn unpack(data: u64) -> TtEntry {
+        let bound = match (data >> 40) & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let mov = data >> 42;
+        let best_move = if mov & (1 << 14) != 0 {
+            Some(Move::new(
+                Position::new_n(((mov >> 7) & 127) as usize),
+                Position::new_n((mov & 127) as usize),
+            ))
+        } else {
+            None
+        };
+
+        TtEntry {
+            depth: ((data >> 32) & 255) as usize,
+            bound,
+            score: f32::from_bits(data as u32) as f64,
+            best_move,
+        }
+    }
+}
+
+impl TranspositionTable {
+    /// Create a table using at most `size_mb` megabytes.
+    /// The number of entries is rounded down to a power of two.
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let max_entries = (size_mb.max(1) * 1024 * 1024 / std::mem::size_of::<TtSlot>()).max(1);
+        let num_entries = 1 << max_entries.ilog2();
+
+        TranspositionTable {
+            slots: (0..num_entries)
+                .map(|_| TtSlot {
+                    key: AtomicU64::new(0),
+                    data: AtomicU64::new(0),
+                })
+                .collect(),
+            mask: num_entries - 1,
+        }
+    }
+
+    /// Returns the entry stored for the hash if there is one
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = &self.slots[key as usize & self.mask];
+        let data = slot.data.load(Ordering::Relaxed);
+
+        // empty slots never verify as the depth of a stored search is at least one
+        if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(TtEntry::unpack(data))
+    }
+
+    /// Stores a search result of the node at the given distance from the root.
+    /// Entries of the same position are only replaced by searches that are at least as deep.
+    pub fn store(
+        &self,
+        key: u64,
+        depth: usize,
+        bound: Bound,
+        score: f64,
+        dist_from_root: usize,
+        best_move: Option<Move>,
+    ) {
+        if let Some(entry) = self.probe(key) {
+            if entry.depth > depth {
+                return;
+            }
+        }
+
+        // wins and losses are stored by their distance from the node, so they hold at any distance from the root
+        let score = if score >= WIN_THRESHOLD {
+            score + dist_from_root as f64
+        } else if score <= -WIN_THRESHOLD {
+            score - dist_from_root as f64
+        } else {
+            score
+        };
+
+        let data = TtEntry {
+            depth,
+            bound,
+            score,
+            best_move,
+        }
+        .pack();
+
+        let slot = &self.slots[key as usize & self.mask];
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(entry: &TtEntry) -> TtEntry {
+        TtEntry::unpack(entry.pack())
+    }
+
+    #[test]
+    fn packed_entry_keeps_all_fields() {
+        let mov = Move::new(Position::new_n(3), Position::new_n(118));
+        for (bound, best_move) in [
+            (Bound::Exact, Some(mov.clone())),
+            (Bound::Lower, None),
+            (Bound::Upper, Some(mov)),
+        ] {
+            let entry = TtEntry {
+                depth: 7,
+                bound,
+                score: -3.5,
+                best_move: best_move.clone(),
+            };
+            let unpacked = round_trip(&entry);
+            assert_eq!(unpacked.get_depth(), 7);
+            assert!(unpacked.get_bound() == bound);
+            assert_eq!(unpacked.get_score(0), -3.5);
+            assert!(*unpacked.get_best_move() == best_move);
+        }
+    }
+
+    #[test]
+    fn packed_depth_is_capped() {
+        let entry = TtEntry {
+            depth: 300,
+            bound: Bound::Exact,
+            score: 0.0,
+            best_move: None,
+        };
+        assert_eq!(round_trip(&entry).get_depth(), 255);
+    }
+
+    #[test]
+    fn deeper_entry_is_not_replaced() {
+        let tt = TranspositionTable::new(1);
+        tt.store(42, 5, Bound::Exact, 1.0, 0, None);
+        tt.store(42, 3, Bound::Lower, 2.0, 0, None);
+        assert_eq!(tt.probe(42).unwrap().get_score(0), 1.0);
+
+        tt.store(42, 6, Bound::Upper, 3.0, 0, None);
+        let entry = tt.probe(42).unwrap();
+        assert_eq!(entry.get_score(0), 3.0);
+        assert!(entry.get_bound() == Bound::Upper);
+        assert!(tt.probe(43).is_none());
+    }
+
+    #[test]
+    fn win_is_probed_by_its_distance_from_the_new_root() {
+        let tt = TranspositionTable::new(1);
+        // the game is won 2 plies below a node at distance 3 from the root
+        tt.store(1, 4, Bound::Exact, WIN_SCORE - 5.0, 3, None);
+        assert_eq!(tt.probe(1).unwrap().get_score(1), WIN_SCORE - 3.0);
+
+        tt.store(2, 4, Bound::Exact, -WIN_SCORE + 5.0, 3, None);
+        assert_eq!(tt.probe(2).unwrap().get_score(6), -WIN_SCORE + 8.0);
+    }
+
+    #[test]
+    fn ordinary_score_does_not_depend_on_the_distance() {
+        let tt = TranspositionTable::new(1);
+        tt.store(1, 4, Bound::Exact, 12.5, 3, None);
+        assert_eq!(tt.probe(1).unwrap().get_score(7), 12.5);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    agent::{
+        info::{InfoCallback, SearchInfo},
+        limits::SearchLimits,
+        trace::SearchTrace,
+        Bot,
+    },
+    game::{board::Board, r#move::Move},
+};
+
+use super::table::OpeningBook;
+
+/// Plays moves from an opening book and hands off to the inner bot
+/// as soon as the position is not in the book anymore.
+pub struct BookBot {
+    book: OpeningBook,
+    inner: Box<dyn Bot>,
+    min_count: usize,
+    last_move_from_book: bool,
+}
+
+impl BookBot {
+    /// Create a new book bot.
+    /// Only book moves that were played at least `min_count` times are considered.
+    pub fn new(book: OpeningBook, inner: Box<dyn Bot>, min_count: usize) -> BookBot {
+        BookBot {
+            book,
+            inner,
+            min_count,
+            last_move_from_book: false,
+        }
+    }
+}
+
+impl Bot for BookBot {
+    fn get_next_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        limits.seed_rng(0);
+        if let Some(mov) = self.book.choose_move(board, self.min_count) {
+            self.last_move_from_book = true;
+            limits.wait_for_stop();
+            return Some(mov);
+        }
+
+        self.last_move_from_book = false;
+        self.inner.get_next_move(board, limits)
+    }
+
+    fn reset(&mut self, board: &Board) {
+        self.inner.reset(board);
+    }
+
+    fn num_nodes(&self) -> usize {
+        if self.last_move_from_book {
+            0
+        } else {
+            self.inner.num_nodes()
+        }
+    }
+
+    fn get_name(&self) -> String {
+        format!("Book{}", self.inner.get_name())
+    }
+
+    fn get_search_stats(&self) -> Option<String> {
+        if self.last_move_from_book {
+            None
+        } else {
+            self.inner.get_search_stats()
+        }
+    }
+
+    fn set_info_callback(&mut self, callback: InfoCallback) {
+        self.inner.set_info_callback(callback);
+    }
+
+    fn get_search_info(&self) -> Option<SearchInfo> {
+        if self.last_move_from_book {
+            None
+        } else {
+            self.inner.get_search_info()
+        }
+    }
+
+    fn ponder(&mut self, board: &Board, limits: &SearchLimits) {
+        self.inner.ponder(board, limits);
+    }
+
+    fn get_search_trace(&self, max_depth: usize, min_visits: u32) -> Option<SearchTrace> {
+        if self.last_move_from_book {
+            None
+        } else {
+            self.inner.get_search_trace(max_depth, min_visits)
+        }
+    }
+}
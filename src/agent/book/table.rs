@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    num::ParseIntError,
+    path::Path,
+};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+
+use crate::{
+    corpus::record::GameRecord,
+    game::{
+        board::{Board, GameState},
+        piece::PieceColor,
+        r#move::{Move, ParseMoveError},
+    },
+    utils::rng,
+};
+
+#[derive(Debug)]
+pub struct ParseBookError {
+    kind: BookErrorKind,
+}
+
+#[derive(Debug)]
+enum BookErrorKind {
+    Io(std::io::Error),
+    WrongDataAmount(usize),
+    IntParsing(usize, ParseIntError),
+    MoveError(usize, ParseMoveError),
+}
+
+/// Statistics of a move played from a book position
+#[derive(Clone)]
+pub struct BookMove {
+    mov: Move,
+    count: usize,
+    attacker_wins: usize,
+    defender_wins: usize,
+    draws: usize,
+}
+
+/// An opening book mapping position hashes to the moves played from them
+pub struct OpeningBook {
+    max_ply: usize,
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl BookMove {
+    /// Create a new book move without any games
+    pub fn new(mov: Move) -> BookMove {
+        BookMove {
+            mov,
+            count: 0,
+            attacker_wins: 0,
+            defender_wins: 0,
+            draws: 0,
+        }
+    }
+
+    /// Returns the score of the move for the given color.
+    /// Wins count 1 and draws as well as unfinished games count 0.5.
+    pub fn win_rate(&self, color: &PieceColor) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let wins = match color {
+            PieceColor::Attacker => self.attacker_wins,
+            PieceColor::Defender => self.defender_wins,
+        };
+        (wins as f64 + 0.5 * self.draws as f64) / self.count as f64
+    }
+
+    /// Adds the outcome of a game to the statistics
+    fn add_result(&mut self, result: &GameState) {
+        self.count += 1;
+        match result {
+            GameState::WinAttacker => self.attacker_wins += 1,
+            GameState::WinDefender => self.defender_wins += 1,
+            GameState::Draw | GameState::Undecided => self.draws += 1,
+        }
+    }
+}
+
+impl OpeningBook {
+    /// Create an empty book storing at most `max_ply` plies of every game
+    pub fn new(max_ply: usize) -> OpeningBook {
+        OpeningBook {
+            max_ply,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Builds a book from the given game records
+    pub fn from_records(records: &[GameRecord], max_ply: usize) -> OpeningBook {
+        let mut book = OpeningBook::new(max_ply);
+        for record in records {
+            book.add_game(record.get_moves(), &record.get_result());
+        }
+        book
+    }
+
+    /// Adds the opening of a game with the given outcome to the book.
+    /// This can be used with replay files as well as self-play games.
+    pub fn add_game(&mut self, moves: &[Move], result: &GameState) {
+        let mut board = Board::new();
+
+        for mov in moves.iter().take(self.max_ply) {
+            let book_moves = self.entries.entry(board.get_hash()).or_default();
+
+            match book_moves.iter_mut().find(|book_mov| book_mov.mov == *mov) {
+                Some(book_mov) => book_mov.add_result(result),
+                None => {
+                    let mut book_mov = BookMove::new(mov.clone());
+                    book_mov.add_result(result);
+                    book_moves.push(book_mov);
+                }
+            }
+
+            board.make_move_captured_positions(mov);
+        }
+    }
+
+    /// Returns the moves stored for the given position
+    pub fn lookup(&self, board: &Board) -> Option<&Vec<BookMove>> {
+        self.entries.get(&board.get_hash())
+    }
+
+    /// Chooses a book move for the given position.
+    /// Moves played at least `min_count` times are chosen randomly,
+    /// weighted by their frequency and their score for the player to move.
+    /// Returns None if the position is not in the book.
+    pub fn choose_move(&self, board: &Board, min_count: usize) -> Option<Move> {
+        let player = board.get_player();
+
+        // moves are checked for legality to guard against hash collisions
+        let candidates: Vec<&BookMove> = self
+            .lookup(board)?
+            .iter()
+            .filter(|book_mov| book_mov.count >= min_count.max(1))
+            .filter(|book_mov| board.is_valid_move(&book_mov.mov, &player))
+            .collect();
+
+        let weights: Vec<f64> = candidates
+            .iter()
+            .map(|book_mov| book_mov.count as f64 * book_mov.win_rate(&player))
+            .collect();
+
+        let dist = WeightedIndex::new(&weights).ok()?;
+        Some(
+            candidates[rng::with_rng(|rng| dist.sample(rng))]
+                .mov
+                .clone(),
+        )
+    }
+
+    /// Returns the number of positions in the book
+    pub fn num_positions(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Saves the book to a file.
+    /// Every line has the format: <hash> <count> <attacker wins> <defender wins> <draws> <move>
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(format!("max_ply {}\n", self.max_ply).as_bytes())?;
+        for (hash, book_moves) in &self.entries {
+            for book_mov in book_moves {
+                writer.write_all(
+                    format!(
+                        "{} {} {} {} {} {}\n",
+                        hash,
+                        book_mov.count,
+                        book_mov.attacker_wins,
+                        book_mov.defender_wins,
+                        book_mov.draws,
+                        book_mov.mov
+                    )
+                    .as_bytes(),
+                )?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Loads a book written by `save`
+    pub fn load(path: &Path) -> Result<OpeningBook, ParseBookError> {
+        let file = File::open(path).map_err(|err| ParseBookError {
+            kind: BookErrorKind::Io(err),
+        })?;
+        let mut book = OpeningBook::new(0);
+
+        for (line_idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| ParseBookError {
+                kind: BookErrorKind::Io(err),
+            })?;
+            let line_num = line_idx + 1;
+            let int_parse = |el: &str| {
+                el.parse::<u64>().map_err(|err| ParseBookError {
+                    kind: BookErrorKind::IntParsing(line_num, err),
+                })
+            };
+
+            if let Some(max_ply) = line.strip_prefix("max_ply") {
+                book.max_ply = int_parse(max_ply.trim())? as usize;
+                continue;
+            }
+
+            let parts: Vec<&str> = line.splitn(6, ' ').collect();
+            if parts.len() != 6 {
+                return Err(ParseBookError {
+                    kind: BookErrorKind::WrongDataAmount(line_num),
+                });
+            }
+
+            let mov = Move::from_record_str(parts[5]).map_err(|err| ParseBookError {
+                kind: BookErrorKind::MoveError(line_num, err),
+            })?;
+
+            book.entries
+                .entry(int_parse(parts[0])?)
+                .or_default()
+                .push(BookMove {
+                    mov,
+                    count: int_parse(parts[1])? as usize,
+                    attacker_wins: int_parse(parts[2])? as usize,
+                    defender_wins: int_parse(parts[3])? as usize,
+                    draws: int_parse(parts[4])? as usize,
+                });
+        }
+
+        Ok(book)
+    }
+}
+
+impl std::fmt::Display for ParseBookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            BookErrorKind::Io(err) => write!(f, "file could not be read: {}", err),
+            BookErrorKind::WrongDataAmount(line) => {
+                write!(f, "line {}: wrong amount of data provided", line)
+            }
+            BookErrorKind::IntParsing(line, err) => {
+                write!(f, "line {}: integers could not be parsed: {}", line, err)
+            }
+            BookErrorKind::MoveError(line, err) => {
+                write!(f, "line {}: move could not be parsed: {}", line, err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    fn mov(text: &str) -> Move {
+        Move::from_record_str(text).unwrap()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("book_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn saved_book_loads_the_same() {
+        let moves = [mov("(7, A) -> (9, A)"), mov("(7, F) -> (7, A)")];
+        let mut book = OpeningBook::new(12);
+        book.add_game(&moves, &GameState::WinAttacker);
+        book.add_game(&moves[..1], &GameState::WinDefender);
+
+        let path = temp_path("round_trip");
+        book.save(&path).unwrap();
+        let loaded = OpeningBook::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.max_ply, 12);
+        assert_eq!(loaded.num_positions(), book.num_positions());
+        let mut board = Board::new();
+        for mov in &moves {
+            let expected = book.lookup(&board).unwrap();
+            let actual = loaded.lookup(&board).unwrap();
+            assert_eq!(actual.len(), expected.len());
+            for (actual, expected) in actual.iter().zip(expected) {
+                assert!(actual.mov == expected.mov);
+                assert_eq!(actual.count, expected.count);
+                assert_eq!(actual.attacker_wins, expected.attacker_wins);
+                assert_eq!(actual.defender_wins, expected.defender_wins);
+                assert_eq!(actual.draws, expected.draws);
+            }
+            board.make_move_captured_positions(mov);
+        }
+    }
+
+    #[test]
+    fn illegal_moves_of_a_loaded_book_are_never_chosen() {
+        let hash = Board::new().get_hash();
+        let path = temp_path("illegal");
+        fs::write(
+            &path,
+            format!(
+                "max_ply 12\n{0} 50 50 0 0 (9, A) -> (9, B)\n{0} 50 50 0 0 (7, A) -> (9, C)\n\
+                 {0} 1 1 0 0 (7, A) -> (9, A)\n",
+                hash
+            ),
+        )
+        .unwrap();
+        let book = OpeningBook::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for _ in 0..20 {
+            assert!(book.choose_move(&Board::new(), 1) == Some(mov("(7, A) -> (9, A)")));
+        }
+        // only the illegal moves are played often enough
+        assert!(book.choose_move(&Board::new(), 2).is_none());
+    }
+}
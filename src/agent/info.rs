@@ -0,0 +1,44 @@
+use std::fmt::Display;
+
+use crate::game::r#move::Move;
+
+/// Called by a bot whenever it has new information about its search
+pub type InfoCallback = Box<dyn FnMut(&SearchInfo) + Send>;
+
+/// What a bot knows about its current search
+#[derive(Clone)]
+pub struct SearchInfo {
+    /// depth the search reached
+    pub depth: usize,
+    /// score from the view of the player to move
+    pub score: f64,
+    /// principal variation, the line the bot expects to be played
+    pub pv: Vec<Move>,
+    /// number of nodes visited so far
+    pub nodes: usize,
+    /// time used so far in ms
+    pub time_ms: u128,
+}
+
+impl SearchInfo {
+    /// Get the number of nodes visited per second
+    pub fn get_nps(&self) -> usize {
+        (self.nodes as u128 * 1000 / self.time_ms.max(1)) as usize
+    }
+}
+
+impl Display for SearchInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pv: Vec<String> = self.pv.iter().map(|mov| mov.to_string()).collect();
+        write!(
+            f,
+            "depth {} score {:.2} nodes {} nps {} time {}ms pv {}",
+            self.depth,
+            self.score,
+            self.nodes,
+            self.get_nps(),
+            self.time_ms,
+            pv.join(" ")
+        )
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread, time,
+};
+
+use crate::{game::clock::SideTime, utils::rng};
+
+/// time in ms between two checks of the stop flag while waiting for it
+const STOP_POLL_MS: u64 = 10;
+
+/// Flag to stop a running search from another thread.
+/// Clones share the same flag.
+#[derive(Clone, Default)]
+pub struct StopFlag {
+    stopped: Arc<AtomicBool>,
+}
+
+/// What ends a search. The search stops as soon as one of the limits is reached
+/// or the stop flag is set. Without any limit it runs until it is stopped.
+#[derive(Clone, Default)]
+pub struct SearchLimits {
+    /// time for the move in ms
+    pub(crate) movetime: Option<u128>,
+    /// nodes the search may visit
+    pub(crate) nodes: Option<usize>,
+    /// depth the search may reach in plies
+    pub(crate) depth: Option<usize>,
+    /// clock of the side to move, the bot decides how much of it to use
+    pub(crate) clock: Option<SideTime>,
+    /// seed of the random choices of the search, so a search limited by nodes
+    /// or depth on one thread makes the same move every time
+    pub(crate) seed: Option<u64>,
+    pub(crate) stop: StopFlag,
+}
+
+impl StopFlag {
+    /// Stops the searches using the flag
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+impl SearchLimits {
+    /// Create limits that give the search the time in ms
+    pub fn movetime(movetime: u128) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+
+    /// Create limits that let the search visit the number of nodes
+    pub fn nodes(nodes: usize) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    /// Create limits that let the search reach the depth
+    pub fn depth(depth: usize) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    /// Create limits that leave the time of the move to the bot, given its clock
+    pub fn clock(time: SideTime) -> SearchLimits {
+        SearchLimits {
+            clock: Some(time),
+            ..Default::default()
+        }
+    }
+
+    /// returns a copy with the clock of the side to move replaced, sharing the stop flag
+    pub fn with_clock(&self, clock: Option<SideTime>) -> SearchLimits {
+        SearchLimits {
+            clock,
+            ..self.clone()
+        }
+    }
+
+    /// returns a copy with the seed of the random choices replaced, sharing the stop flag
+    pub fn with_seed(&self, seed: Option<u64>) -> SearchLimits {
+        SearchLimits {
+            seed,
+            ..self.clone()
+        }
+    }
+
+    /// Seeds the random choices of the calling search thread if the limits have a seed.
+    /// Every thread gets its own seed from its index.
+    pub fn seed_rng(&self, thread_idx: usize) {
+        if let Some(seed) = self.seed {
+            rng::seed(seed.wrapping_add(thread_idx as u64));
+        }
+    }
+
+    /// Create limits that let the search run until it is stopped
+    pub fn infinite() -> SearchLimits {
+        SearchLimits::default()
+    }
+
+    /// returns true if no limit is set, so only the stop flag ends the search
+    pub fn is_infinite(&self) -> bool {
+        self.movetime.is_none()
+            && self.nodes.is_none()
+            && self.depth.is_none()
+            && self.clock.is_none()
+    }
+
+    /// returns true if the search has been stopped or has used up its time
+    pub fn time_is_up(&self, start_time: &time::Instant) -> bool {
+        self.stop.is_stopped()
+            || self
+                .movetime
+                .is_some_and(|movetime| start_time.elapsed().as_millis() >= movetime)
+    }
+
+    /// returns true if the search has to stop after visiting the nodes
+    pub fn is_reached(&self, start_time: &time::Instant, nodes: usize) -> bool {
+        self.time_is_up(start_time) || self.nodes.is_some_and(|max_nodes| nodes >= max_nodes)
+    }
+
+    /// returns true if the search may not go deeper than the depth
+    pub fn depth_is_reached(&self, depth: usize) -> bool {
+        self.depth.is_some_and(|max_depth| depth >= max_depth)
+    }
+
+    /// Blocks an infinite search until it is stopped, so a bot that finished
+    /// early does not answer before it is asked to
+    pub fn wait_for_stop(&self) {
+        while self.is_infinite() && !self.stop.is_stopped() {
+            thread::sleep(time::Duration::from_millis(STOP_POLL_MS));
+        }
+    }
+}
+
+/// Short form of the limits that contains no '-', e.g. "300" for a movetime,
+/// "n5000" for nodes, "d4" for a depth, "c60000i500" for a clock with increment,
+/// or "inf", and "s42" for a seed, joined by '+'
+impl Display for SearchLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(movetime) = self.movetime {
+            parts.push(movetime.to_string());
+        }
+        if let Some(nodes) = self.nodes {
+            parts.push(format!("n{}", nodes));
+        }
+        if let Some(depth) = self.depth {
+            parts.push(format!("d{}", depth));
+        }
+        if let Some(clock) = self.clock {
+            parts.push(format!("c{}i{}", clock.remaining_ms, clock.increment_ms));
+        }
+        if parts.is_empty() {
+            parts.push("inf".to_string());
+        }
+        if let Some(seed) = self.seed {
+            parts.push(format!("s{}", seed));
+        }
+        write!(f, "{}", parts.join("+"))
+    }
+}
@@ -1,2 +1,4 @@
-mod node;
+pub mod node;
 pub mod policy;
+pub mod prior;
+mod tree;
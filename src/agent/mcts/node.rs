@@ -1,188 +1,274 @@
-use rand::seq::SliceRandom;
-use rand::thread_rng;
-
 use std::fmt::Display;
-use std::sync::{Arc, RwLock, Weak};
 
-use crate::game::board::Board;
-use crate::game::piece::Piece;
 use crate::game::r#move::Move;
 
+/// outcome counted for every thread that is currently searching below a node
+const VIRTUAL_LOSS: f64 = 1.0;
+
+/// Index of a node in the arena of its tree
+pub type NodeId = u32;
+
+/// Marks a node whose children have not been allocated yet.
+/// The root is the first node of the arena, so no children can start there.
+pub const NO_CHILDREN: NodeId = 0;
+
+/// Game-theoretic value of a node from the view of the player who moved into it
+#[derive(Clone, Copy, PartialEq)]
+pub enum Proven {
+    Unknown,
+    Win,
+    Loss,
+}
+
+/// Statistics of a child node, so the search can be inspected without printing the tree
 #[derive(Clone)]
-pub struct MctsTreenode {
-    terminal: bool,
-    state: Board,
+pub struct ChildStats {
+    pub mov: Move,
+    pub visits: u32,
+    /// mean outcome from the view of the player who played the move
+    pub mean: f64,
+    pub prior: f64,
+    pub proven: Proven,
+}
+
+/// A node of the MCTS tree.
+/// The board is not stored, it is recomputed from the moves on the path from the root.
+#[derive(Clone)]
+pub struct MctsNode {
     mov: Option<Move>, //move used to get to this state :D
-    unexplored_moves: Vec<Move>,
-    unexplored_moves_index: usize,
     q_val: f64,
-    n_val: usize,
-    children: Vec<TreenodeRefStrong>,
-    parent: Option<TreenodeRefWeak>,
+    n_val: u32,
+    virtual_loss: u32,
+    /// all-moves-as-first statistics: outcomes of the simulations through the parent
+    /// in which the move was played later on by the same player
+    amaf_q_val: f64,
+    amaf_n_val: u32,
+    /// prior probability of the move, used by PUCT
+    prior: f32,
+    /// the children are stored contiguously from here on
+    first_child: NodeId,
+    num_children: u32,
+    /// the first `num_expanded` children have been expanded, the others are still unexplored
+    num_expanded: u32,
+    terminal: bool,
+    proven: Proven,
 }
 
-pub type TreenodeRefWeak = Weak<RwLock<MctsTreenode>>;
-pub type TreenodeRefStrong = Arc<RwLock<MctsTreenode>>;
-
-impl MctsTreenode {
-    pub fn new_root(state: Board) -> MctsTreenode {
-        let unexplored_moves = state.get_legal_moves();
-        MctsTreenode {
-            terminal: state.is_game_over(),
-            state,
-            mov: None,
-            unexplored_moves,
-            unexplored_moves_index: 0,
+impl MctsNode {
+    /// Creates a node for the position after the move that has not been expanded yet
+    pub fn new(mov: Option<Move>) -> MctsNode {
+        MctsNode {
+            mov,
             q_val: 0.0,
             n_val: 0,
-            children: vec![],
-            parent: None,
+            virtual_loss: 0,
+            amaf_q_val: 0.0,
+            amaf_n_val: 0,
+            prior: 0.0,
+            first_child: NO_CHILDREN,
+            num_children: 0,
+            num_expanded: 0,
+            terminal: false,
+            proven: Proven::Unknown,
         }
     }
 
-    /// Creates a new child node
-    pub fn new_child_node(parent: &TreenodeRefWeak, mov: Move) -> MctsTreenode {
-        let parent_borrowed = parent.upgrade().unwrap();
-
-        let mut child_state = parent_borrowed.read().unwrap().state.clone();
+    /// Computes the UCB1 value of itself.
+    /// Threads searching below the node count as lost visits, so other threads choose different paths.
+    pub fn compute_ucb_val(
+        &self,
+        expl_param: f64,
+        nvf_parent: f64,
+        rave_equivalence: Option<f64>,
+    ) -> f64 {
+        let nvf = (self.n_val + self.virtual_loss) as f64;
 
-        // make move on child state and get captured positions
-        child_state.make_move_captured_positions(&mov);
+        self.compute_mean(rave_equivalence) + expl_param * (2.0 * nvf_parent.ln() / nvf).sqrt()
+    }
 
-        // get unexplored moves and shuffle them to get better results
-        let mut unexplored_moves = child_state.get_legal_moves();
-        unexplored_moves.shuffle(&mut thread_rng());
+    /// Computes the PUCT value of itself, where the exploration is guided by the prior.
+    /// Nodes that have not been visited yet are valued as a draw.
+    pub fn compute_puct_val(
+        &self,
+        expl_param: f64,
+        nvf_parent: f64,
+        rave_equivalence: Option<f64>,
+    ) -> f64 {
+        let nvf = (self.n_val + self.virtual_loss) as f64;
+        let mean = if nvf > 0.0 {
+            self.compute_mean(rave_equivalence)
+        } else {
+            0.0
+        };
 
-        MctsTreenode {
-            terminal: child_state.is_game_over(),
-            state: child_state,
-            mov: Some(mov),
-            unexplored_moves,
-            unexplored_moves_index: 0,
-            q_val: 0.0,
-            n_val: 0,
-            children: vec![],
-            parent: Some(Weak::clone(parent)),
-        }
+        mean + expl_param * self.prior as f64 * nvf_parent.sqrt() / (1.0 + nvf)
     }
 
-    /// Returns a reference to the next best child node by the UCB1 formula
-    pub fn get_next_child_ucb(&self, expl_param: f64) -> Option<TreenodeRefStrong> {
-        let mut maxucbval = f64::NEG_INFINITY;
-        let mut maxchild: Option<TreenodeRefStrong> = None;
+    /// Computes the mean outcome counting the virtual loss.
+    /// With RAVE, it is blended with the all-moves-as-first mean,
+    /// whose weight shrinks with the visits and is one half after `rave_equivalence` visits.
+    fn compute_mean(&self, rave_equivalence: Option<f64>) -> f64 {
+        let nvf = (self.n_val + self.virtual_loss) as f64;
+        let qvf = self.q_val - VIRTUAL_LOSS * self.virtual_loss as f64;
 
-        for child in &self.children {
-            let child_ucb_val = child.read().unwrap().compute_ucb_val(expl_param);
-            if child_ucb_val > maxucbval {
-                maxucbval = child_ucb_val;
-                maxchild = Some(Arc::clone(child));
+        let mut mean = qvf / nvf;
+        if let Some(equivalence) = rave_equivalence {
+            if self.amaf_n_val > 0 {
+                let beta = (equivalence / (3.0 * nvf + equivalence)).sqrt();
+                let amaf_mean = self.amaf_q_val / self.amaf_n_val as f64;
+                mean = (1.0 - beta) * mean + beta * amaf_mean;
             }
         }
-
-        Some(Arc::clone(&maxchild?))
+        mean
     }
 
-    /// Computes the UCB1 value of itself.
-    fn compute_ucb_val(&self, expl_param: f64) -> f64 {
-        let nvf = self.n_val as f64;
-        let nvf_parent = self
-            .parent
-            .as_ref()
-            .unwrap()
-            .upgrade()
-            .unwrap()
-            .read()
-            .unwrap()
-            .n_val as f64;
-
-        self.q_val / nvf + expl_param * (2.0 * nvf_parent.ln() / nvf).sqrt()
-    }
-
-    /// performs back propagation from node switching the sign in each layer
-    /// to accomodate for a two-player-game.
-    pub fn back_propagation(&mut self, outcome: f64) {
+    /// Adds the outcome of a playout to the statistics of the node
+    pub fn update(&mut self, outcome: f64) {
         self.n_val += 1;
         self.q_val += outcome;
+    }
 
-        if let Some(parent) = &self.parent {
-            parent
-                .upgrade()
-                .unwrap()
-                .write()
-                .unwrap()
-                .back_propagation(-1.0 * outcome);
-        }
+    /// Adds the outcome of a simulation in which the move was played later on
+    pub fn update_amaf(&mut self, outcome: f64) {
+        self.amaf_n_val += 1;
+        self.amaf_q_val += outcome;
     }
 
-    /// Chooses the next move to be expanded.
-    /// Here we just expand the moves in the given order
-    /// This could be improved by using some heuristics.
-    pub fn choose_move(&mut self) -> Option<Move> {
-        let pre_i = self.unexplored_moves_index;
-        if pre_i >= self.unexplored_moves.len() {
-            return None;
-        }
-        self.unexplored_moves_index += 1;
-        Some(self.unexplored_moves[pre_i].clone())
+    /// Adds the statistics of the same node of another tree
+    pub fn add_stats(&mut self, other: &MctsNode) {
+        self.q_val += other.q_val;
+        self.n_val += other.n_val;
+        self.amaf_q_val += other.amaf_q_val;
+        self.amaf_n_val += other.amaf_n_val;
+    }
+
+    /// Marks that a thread is searching below the node
+    pub fn add_virtual_loss(&mut self) {
+        self.virtual_loss += 1;
+    }
+
+    /// Removes the mark of a thread that finished its search below the node
+    pub fn remove_virtual_loss(&mut self) {
+        self.virtual_loss -= 1;
+    }
+
+    /// Returns true if the children of the node have been allocated
+    pub fn has_children(&self) -> bool {
+        self.first_child != NO_CHILDREN
+    }
+
+    /// Stores where the children of the node are located in the arena and how many have been expanded
+    pub fn set_children(&mut self, first_child: NodeId, num_children: u32, num_expanded: u32) {
+        self.first_child = first_child;
+        self.num_children = num_children;
+        self.num_expanded = num_expanded;
+    }
+
+    /// Marks the next unexplored child as expanded
+    pub fn expand_next(&mut self) {
+        self.num_expanded += 1;
+    }
+
+    /// Turns the node into a root by forgetting the move that led to it
+    pub fn make_root(&mut self) {
+        self.mov = None;
+    }
+
+    /// Forgets the children of the node, e.g. after the node was copied into another tree
+    pub fn clear_children(&mut self) {
+        self.first_child = NO_CHILDREN;
+    }
+
+    pub fn set_prior(&mut self, prior: f64) {
+        self.prior = prior as f32;
+    }
+
+    pub fn get_prior(&self) -> f64 {
+        self.prior as f64
+    }
+
+    /// Returns the statistics of the node, None for a root that has no move
+    pub fn get_stats(&self) -> Option<ChildStats> {
+        let mean = if self.n_val > 0 {
+            self.q_val / self.n_val as f64
+        } else {
+            0.0
+        };
+        self.mov.as_ref().map(|mov| ChildStats {
+            mov: mov.clone(),
+            visits: self.n_val,
+            mean,
+            prior: self.get_prior(),
+            proven: self.proven,
+        })
+    }
+
+    pub fn set_terminal(&mut self, terminal: bool) {
+        self.terminal = terminal;
     }
 
     pub fn is_terminal(&self) -> bool {
         self.terminal
     }
 
-    pub fn get_mov(&self) -> &Option<Move> {
-        &self.mov
+    pub fn set_proven(&mut self, proven: Proven) {
+        self.proven = proven;
     }
 
-    pub fn num_movs(&self) -> usize {
-        self.unexplored_moves.len()
+    pub fn get_proven(&self) -> Proven {
+        self.proven
     }
 
-    pub fn get_unexplored_moves_idx(&self) -> usize {
-        self.unexplored_moves_index
+    pub fn get_mov(&self) -> &Option<Move> {
+        &self.mov
     }
 
     pub fn get_q_val(&self) -> f64 {
         self.q_val
     }
 
-    pub fn get_n_val(&self) -> usize {
+    #[cfg(test)]
+    pub fn get_amaf_q_val(&self) -> f64 {
+        self.amaf_q_val
+    }
+
+    pub fn get_n_val(&self) -> u32 {
         self.n_val
     }
 
-    pub fn get_children(&self) -> &Vec<TreenodeRefStrong> {
-        &self.children
+    pub fn get_virtual_loss(&self) -> u32 {
+        self.virtual_loss
+    }
+
+    pub fn get_first_child(&self) -> NodeId {
+        self.first_child
+    }
+
+    pub fn num_children(&self) -> u32 {
+        self.num_children
     }
 
-    pub fn add_child(&mut self, new_child: MctsTreenode) {
-        self.children.push(Arc::new(RwLock::new(new_child)));
+    pub fn num_expanded(&self) -> u32 {
+        self.num_expanded
     }
 
-    pub fn get_board(&self) -> &Board {
-        &self.state
+    /// Returns true if all children have been expanded
+    pub fn is_fully_expanded(&self) -> bool {
+        self.has_children() && self.num_expanded == self.num_children
     }
 }
 
-impl Display for MctsTreenode {
+impl Display for ChildStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
+        write!(
             f,
-            "color: {}, Qv: {}, nv: {}, #children: {}",
-            Piece::Pawn(self.get_board().get_player().clone()),
-            self.q_val,
-            self.n_val,
-            self.children.len(),
-            // self.state
+            "move: {}, # played: {}, mean: {:.3}, prior: {:.3}",
+            self.mov, self.visits, self.mean, self.prior
         )?;
-        for (idx, child) in self.children.iter().enumerate() {
-            writeln!(
-                f,
-                "{idx}: Q_val: {}, # played: {}, move: {}",
-                child.read().unwrap().q_val,
-                child.read().unwrap().n_val,
-                child.read().unwrap().mov.as_ref().unwrap()
-            )?;
+        match self.proven {
+            Proven::Unknown => Ok(()),
+            Proven::Win => write!(f, ", proven win"),
+            Proven::Loss => write!(f, ", proven loss"),
         }
-        Ok(())
     }
 }
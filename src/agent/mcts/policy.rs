@@ -1,148 +1,291 @@
-use std::sync::{Arc, RwLock};
-use std::time;
-
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::{thread, time};
+
+use crate::agent::info::{InfoCallback, SearchInfo};
+use crate::agent::limits::SearchLimits;
+use crate::agent::time_manager::TimeManager;
+use crate::agent::trace::SearchTrace;
 use crate::agent::{Bot, BotInit};
 use crate::eval::Eval;
 use crate::game::piece::PieceColor;
 use crate::game::{board::Board, r#move::Move};
 
-use super::node::{MctsTreenode, TreenodeRefWeak};
+use super::node::{ChildStats, NodeId, Proven};
+use super::prior::Prior;
+use super::tree::{MctsTree, TreeParams, ROOT};
+
+/// time in ms between two search information reports
+const INFO_INTERVAL_MS: u128 = 1000;
+/// time in ms between two checks whether the search should end early
+const CHECK_INTERVAL_MS: u128 = 50;
+/// weight of the confidence interval that is subtracted from the mean by the secure child
+const SECURE_CONFIDENCE: f64 = 1.0;
+/// expanded nodes the tree may have at most after pondering.
+/// Every expanded node allocates all of its children, so this bounds the memory while the opponent thinks.
+const MAX_PONDER_TREE_NODES: usize = 100_000;
+
+/// How several threads share the search
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParallelMode {
+    /// all threads grow the same tree and virtual loss spreads them over it
+    Tree,
+    /// every thread grows its own tree and the roots are merged at the end
+    Root,
+}
+
+/// How the move is chosen from the children of the root after the search
+#[derive(Clone, Copy, PartialEq)]
+pub enum MoveSelection {
+    /// the child with the highest mean outcome
+    Max,
+    /// the child with the most visits
+    Robust,
+    /// the child with the highest lower confidence bound of the mean
+    Secure,
+}
+
+pub struct MctsParams {
+    pub(crate) exploration_param: f64,
+    pub(crate) reuse_tree: bool,
+    pub(crate) num_threads: usize,
+    pub(crate) parallel_mode: ParallelMode,
+    pub(crate) move_selection: MoveSelection,
+    pub(crate) use_solver: bool,
+    pub(crate) rave_equivalence: Option<f64>,
+    /// prior probabilities of the moves, PUCT is used instead of UCT if given
+    pub(crate) prior: Option<Box<dyn Prior + Send + Sync>>,
+}
 
 pub struct Mcts<T: Eval> {
     exploration_param: f64,
-    tree_root: Arc<RwLock<MctsTreenode>>,
+    reuse_tree: bool,
+    num_threads: usize,
+    parallel_mode: ParallelMode,
+    move_selection: MoveSelection,
+    tree_params: TreeParams,
+    prior: Option<Box<dyn Prior + Send + Sync>>,
+    tree: Mutex<MctsTree>,
     num_nodes: usize,
+    reused_visits: usize,
     eval_fn: T,
+    info: Option<SearchInfo>,
+    info_callback: Option<InfoCallback>,
 }
 
-impl<T: Eval> Mcts<T> {
-    /// resets the MctsBot to a certain state.
-    /// Here, the exploration parameter can be reset as well as the board and color.
-    pub fn reset_to(&mut self, exploration_param: f64, board: &Board) {
-        self.exploration_param = exploration_param;
-        self.tree_root = Arc::new(RwLock::new(MctsTreenode::new_root(board.clone())));
-        self.num_nodes = 0;
+impl MctsParams {
+    /// Create the default parameters for the given exploration parameter.
+    /// The tree is reused between moves, proven results are propagated and the search is single-threaded.
+    /// The most visited child is played.
+    /// Plain UCT is used without RAVE and priors.
+    pub fn new(exploration_param: f64) -> MctsParams {
+        MctsParams {
+            exploration_param,
+            reuse_tree: true,
+            num_threads: 1,
+            parallel_mode: ParallelMode::Tree,
+            move_selection: MoveSelection::Robust,
+            use_solver: true,
+            rave_equivalence: None,
+            prior: None,
+        }
     }
+}
 
-    /// returns the best possible move from the root node after
-    /// children have been calculated.
-    /// If this is executed before the first child extention, it will simply return None.
-    pub fn get_best_move(&self) -> Option<Move> {
-        let mut max_eval = f64::MIN;
-        let mut incumbent_mov: Option<Move> = None;
-
-        let root_borrowed = &self.tree_root.read().unwrap();
-
-        for child in root_borrowed.get_children().iter() {
-            let child_q_val = child.read().unwrap().get_q_val();
-            let child_n_val = child.read().unwrap().get_n_val() as f64;
+/// Everything a search thread needs to grow a tree
+struct Worker<'a, T: Eval> {
+    eval_fn: &'a T,
+    exploration_param: f64,
+    start_time: time::Instant,
+    limits: &'a SearchLimits,
+    nodes: &'a AtomicUsize,
+    /// deepest ply a selected path has reached
+    depth: &'a AtomicUsize,
+    /// set as soon as one thread decides that the search is done
+    finished: &'a AtomicBool,
+    /// whether the moves of the rollouts are needed by the tree
+    record_playout: bool,
+    prior: Option<&'a (dyn Prior + Send + Sync)>,
+}
 
-            let eval = child_q_val / child_n_val;
+impl<T: Eval> Worker<'_, T> {
+    /// performs MCTS iterations on the tree until one of the limits is reached.
+    /// The root is always expanded, so there is a move even if the time is already up.
+    /// `check` is called in regular intervals and returns false to end the search of all threads.
+    fn run(&self, tree: &Mutex<MctsTree>, mut check: impl FnMut() -> bool) {
+        let mut last_check = 0;
+        let mut path = vec![];
+        let mut playout = vec![];
+
+        while tree.lock().unwrap().get_node(ROOT).get_n_val() == 0
+            || !self.finished.load(Ordering::Relaxed)
+                && !self
+                    .limits
+                    .is_reached(&self.start_time, self.nodes.load(Ordering::Relaxed))
+                && !self
+                    .limits
+                    .depth_is_reached(self.depth.load(Ordering::Relaxed))
+        {
+            if self.start_time.elapsed().as_millis() >= last_check + CHECK_INTERVAL_MS {
+                last_check = self.start_time.elapsed().as_millis();
+                if !check() {
+                    self.finished.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
 
-            if eval > max_eval {
-                incumbent_mov = Some(child.read().unwrap().get_mov().as_ref().unwrap().clone());
-                max_eval = eval;
+            if !self.iteration(tree, &mut path, &mut playout) {
+                break;
             }
         }
-
-        incumbent_mov
-    }
-
-    #[allow(unused)]
-    pub fn print_root(&self) {
-        println!("{}", self.tree_root.read().unwrap());
     }
 
-    /// performs as many MCTS iterations as possible within the given time horizon.
-    pub fn grow_with_time_limit(&mut self, time_limit: u128)
-    where
-        T: Eval,
-    {
-        let start_time = time::Instant::now();
-
-        while start_time.elapsed().as_millis() < time_limit {
-            // get next node to expand and move to be expanded
-            let node_to_expand = self
-                .tree_policy(self.exploration_param)
-                .expect("Tree policy failed.");
-
-            let rc_pointer_node = node_to_expand.upgrade().unwrap();
-            let term = rc_pointer_node.read().unwrap().is_terminal();
+    /// performs one selection, expansion, rollout and back propagation.
+    /// The tree is only locked while it is walked, so the rollouts of the threads run in parallel.
+    /// Returns false if the tree can not grow anymore.
+    fn iteration(
+        &self,
+        tree: &Mutex<MctsTree>,
+        path: &mut Vec<NodeId>,
+        playout: &mut Vec<Move>,
+    ) -> bool {
+        let (board, expanded) = {
+            let mut tree = tree.lock().unwrap();
+            let mut board = tree.get_board().clone();
+
+            // get next node to expand
+            if !tree.select(self.exploration_param, path, &mut board) {
+                return false;
+            }
 
             // if the node is terminal, we find out who won and propagate backwards.
-            if term {
-                let mut node_to_expand_borrowed = rc_pointer_node.write().unwrap();
-
-                let eval = self.eval_fn.get_eval(node_to_expand_borrowed.get_board());
-
-                let outcome = match node_to_expand_borrowed.get_board().get_player() {
-                    PieceColor::Attacker => -eval,
-                    PieceColor::Defender => eval,
-                };
-                node_to_expand_borrowed.back_propagation(outcome);
-                continue;
+            // Otherwise a new child is created.
+            let leaf = *path.last().unwrap();
+            let mut expanded = false;
+            if !tree.get_node(leaf).is_terminal() {
+                if let Some(child) = tree.expand(
+                    leaf,
+                    &mut board,
+                    self.prior.map(|prior| prior as &dyn Prior),
+                ) {
+                    path.push(child);
+                    expanded = true;
+                }
             }
+            self.depth.fetch_max(path.len() - 1, Ordering::Relaxed);
+            (board, expanded)
+        };
+
+        // find out who won and generate outcome
+        playout.clear();
+        let eval = if self.record_playout {
+            self.eval_fn.get_eval_playout(&board, playout)
+        } else {
+            self.eval_fn.get_eval(&board)
+        };
+        let outcome = outcome_for_mover(&board, eval);
+
+        // popagate the reward through the tree
+        tree.lock().unwrap().backup(path, outcome, playout);
+
+        if expanded {
+            self.nodes.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+}
 
-            // choose the next move todo
-            let next_move = rc_pointer_node
-                .write()
-                .unwrap()
-                .choose_move()
-                .expect("No move found.");
+impl<T: Eval> Mcts<T> {
+    /// resets the MctsBot to a certain state.
+    /// Here, the exploration parameter can be reset as well as the board and color.
+    pub fn reset_to(&mut self, exploration_param: f64, board: &Board) {
+        self.exploration_param = exploration_param;
+        self.tree = Mutex::new(MctsTree::new(board.clone(), self.tree_params));
+        self.num_nodes = 0;
+        self.reused_visits = 0;
+        self.info = None;
+    }
 
-            // create a new child node
-            let mut new_child = MctsTreenode::new_child_node(&node_to_expand, next_move);
+    /// moves the root to the node of the given board, so its statistics are kept.
+    /// Only the root and the two plies below it are searched, which covers our last move
+    /// and the reply of the opponent. Returns false if the board is not in the tree.
+    pub fn advance_to(&mut self, board: &Board) -> bool {
+        let tree = self.tree.get_mut().unwrap();
+        let Some(node) = tree.find_descendant(board, 2) else {
+            return false;
+        };
+
+        // copying the subtree frees the rest of the arena
+        *tree = tree.subtree(node, board.clone());
+        self.num_nodes = 0;
+        self.reused_visits = tree.get_node(ROOT).get_n_val() as usize;
+        self.info = None;
+        true
+    }
 
-            // find out who won and generate outcome
-            let eval = self.eval_fn.get_eval(new_child.get_board());
+    /// returns the best possible move from the root node after
+    /// children have been calculated, chosen by the configured move selection.
+    /// A proven win is always played and proven losses are only played if every move loses.
+    /// If this is executed before the first child extention, it will simply return None.
+    pub fn get_best_move(&self) -> Option<Move> {
+        let stats = self.root_child_stats();
 
-            let outcome = match new_child.get_board().get_player() {
-                PieceColor::Attacker => -eval,
-                PieceColor::Defender => eval,
-            };
+        if let Some(win) = stats.iter().find(|child| child.proven == Proven::Win) {
+            return Some(win.mov.clone());
+        }
 
-            // popagate the reward through the tree
-            new_child.back_propagation(outcome);
+        let candidates = stats
+            .iter()
+            .filter(|child| child.proven != Proven::Loss && child.visits > 0);
+        let best = match self.move_selection {
+            MoveSelection::Max => candidates.max_by(|a, b| a.mean.total_cmp(&b.mean)),
+            MoveSelection::Robust => {
+                candidates.max_by(|a, b| a.visits.cmp(&b.visits).then(a.mean.total_cmp(&b.mean)))
+            }
+            MoveSelection::Secure => {
+                candidates.max_by(|a, b| secure_value(a).total_cmp(&secure_value(b)))
+            }
+        };
 
-            // add child to the parent node
-            rc_pointer_node.write().unwrap().add_child(new_child);
+        best.or_else(|| stats.iter().max_by_key(|child| child.visits))
+            .map(|child| child.mov.clone())
+    }
 
-            self.num_nodes += 1;
-        }
+    /// returns the statistics of the explored moves of the root
+    pub fn root_child_stats(&self) -> Vec<ChildStats> {
+        self.tree.lock().unwrap().child_stats(ROOT)
     }
 
-    /// performs the tree policy on the MCTS tree yielding the next node to expand.
-    /// If all nodes have been expanded, it will return None.
-    fn tree_policy(&self, expl_param: f64) -> Option<TreenodeRefWeak> {
-        let mut current_node = Arc::clone(&self.tree_root);
+    /// returns the number of visits of the root, which is the number of iterations
+    /// of all threads since the tree was created, including those that ended in terminal nodes
+    pub fn root_visits(&self) -> usize {
+        self.tree.lock().unwrap().get_node(ROOT).get_n_val() as usize
+    }
 
-        loop {
-            // get next index of move
-            let i = current_node.read().unwrap().get_unexplored_moves_idx();
-            let term = current_node.read().unwrap().is_terminal();
+    /// returns the number of expanded nodes in the tree
+    pub fn num_tree_nodes(&self) -> usize {
+        self.tree.lock().unwrap().num_expanded_nodes()
+    }
 
-            // if the current node is terminal or there are still some moves not expanded -> return
-            if i < current_node.read().unwrap().num_movs() || term {
-                return Some(Arc::downgrade(&current_node));
-            }
+    /// returns the number of bytes used by the tree
+    pub fn tree_memory_usage(&self) -> usize {
+        self.tree.lock().unwrap().memory_usage()
+    }
 
-            // get the next child node by using ucb
-            let next_node = current_node
-                .read()
-                .unwrap()
-                .get_next_child_ucb(expl_param)?;
-            current_node = next_node;
-        }
+    #[allow(unused)]
+    pub fn print_root(&self) {
+        println!("{}", self.tree.lock().unwrap());
     }
 
     /// returns the number of nodes in the tree
     #[allow(unused)]
     pub fn compute_depth(&self) -> usize {
-        let mut current = Arc::clone(&self.tree_root);
+        let tree = self.tree.lock().unwrap();
+        let mut current = ROOT;
         let mut counter = 0;
 
         loop {
-            let next_child = current.read().unwrap().get_children().first().cloned();
+            let next_child = tree.expanded_children(current).next();
 
             match next_child {
                 Some(child) => {
@@ -155,28 +298,127 @@ impl<T: Eval> Mcts<T> {
     }
 }
 
+impl<T: Eval + Sync> Mcts<T> {
+    /// performs MCTS iterations until one of the limits is reached.
+    /// The nodes are the expanded nodes and the depth is the deepest ply a selection reached.
+    /// With a clock, the time manager decides when the most visited move is stable enough.
+    /// With several threads the tree is grown in the configured parallel mode.
+    pub fn grow(&mut self, limits: &SearchLimits) {
+        let (mut time_manager, planned_limits) =
+            TimeManager::new(limits, self.tree.get_mut().unwrap().get_board());
+        let start_time = time::Instant::now();
+        let nodes = AtomicUsize::new(0);
+        let depth = AtomicUsize::new(0);
+        let finished = AtomicBool::new(false);
+        let worker = Worker {
+            eval_fn: &self.eval_fn,
+            exploration_param: self.exploration_param,
+            start_time,
+            limits: &planned_limits,
+            nodes: &nodes,
+            depth: &depth,
+            finished: &finished,
+            record_playout: self.tree_params.rave_equivalence.is_some(),
+            prior: self.prior.as_deref(),
+        };
+
+        let tree = &self.tree;
+        let mut callback = self.info_callback.take();
+        let mut last_report = 0;
+        let mut check = || {
+            let tree = tree.lock().unwrap();
+            if start_time.elapsed().as_millis() >= last_report + INFO_INTERVAL_MS {
+                last_report = start_time.elapsed().as_millis();
+                let info = search_info(&tree, nodes.load(Ordering::Relaxed), &start_time);
+                if let Some(callback) = callback.as_mut() {
+                    callback(&info);
+                }
+            }
+            let best_move = tree
+                .most_visited_child(ROOT)
+                .and_then(|child| tree.get_node(child).get_mov().clone());
+            !time_manager.should_stop(best_move.as_ref())
+        };
+
+        // in root parallelism every helper thread grows its own tree of the same position
+        let helper_trees: Vec<Mutex<MctsTree>> = match self.parallel_mode {
+            ParallelMode::Tree => vec![],
+            ParallelMode::Root => {
+                let board = tree.lock().unwrap().get_board().clone();
+                (1..self.num_threads)
+                    .map(|_| Mutex::new(MctsTree::new(board.clone(), self.tree_params)))
+                    .collect()
+            }
+        };
+
+        thread::scope(|scope| {
+            for idx in 1..self.num_threads {
+                let worker = &worker;
+                let helper_tree = helper_trees.get(idx - 1).unwrap_or(tree);
+                scope.spawn(move || {
+                    limits.seed_rng(idx);
+                    worker.run(helper_tree, || true)
+                });
+            }
+            limits.seed_rng(0);
+            worker.run(tree, &mut check);
+        });
+
+        let tree = self.tree.get_mut().unwrap();
+        for helper_tree in helper_trees {
+            tree.merge(&helper_tree.into_inner().unwrap());
+        }
+
+        let info = search_info(tree, nodes.load(Ordering::Relaxed), &start_time);
+        if let Some(callback) = callback.as_mut() {
+            callback(&info);
+        }
+
+        self.info = Some(info);
+        self.info_callback = callback;
+        self.num_nodes = nodes.load(Ordering::Relaxed);
+    }
+}
+
 impl<T: Eval> BotInit for Mcts<T> {
     type Ev = T;
-    type Params = f64;
+    type Params = MctsParams;
 
     /// creates a new MctsBot.
     /// For this, an exporation_parameter must be provided which will be used in
     /// the UCB_1 formula for decision-making in the tree-policy of MCTS.
-    /// Moreover, the color of the root node must be provided.
     fn new(bot_params: Self::Params, eval_fn: T) -> Self {
+        let tree_params = TreeParams {
+            use_solver: bot_params.use_solver,
+            rave_equivalence: bot_params.rave_equivalence,
+            use_puct: bot_params.prior.is_some(),
+        };
         Mcts {
-            exploration_param: bot_params,
-            tree_root: Arc::new(RwLock::new(MctsTreenode::new_root(Board::new()))),
+            exploration_param: bot_params.exploration_param,
+            reuse_tree: bot_params.reuse_tree,
+            num_threads: bot_params.num_threads.max(1),
+            parallel_mode: bot_params.parallel_mode,
+            move_selection: bot_params.move_selection,
+            tree_params,
+            prior: bot_params.prior,
+            tree: Mutex::new(MctsTree::new(Board::new(), tree_params)),
             num_nodes: 0,
+            reused_visits: 0,
             eval_fn,
+            info: None,
+            info_callback: None,
         }
     }
 }
 
-impl<T: Eval> Bot for Mcts<T> {
-    fn get_next_move(&mut self, board: &Board, time_limit: u128) -> Option<Move> {
-        self.reset_to(self.exploration_param, board);
-        self.grow_with_time_limit(time_limit);
+impl<T: Eval + Send + Sync> Bot for Mcts<T> {
+    fn get_next_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        if !(self.reuse_tree && self.advance_to(board)) {
+            self.reset_to(self.exploration_param, board);
+        }
+        self.grow(limits);
+        // a solved tree stops growing, but an infinite search still waits to be stopped
+        limits.wait_for_stop();
         self.get_best_move()
     }
 
@@ -189,6 +431,96 @@ impl<T: Eval> Bot for Mcts<T> {
     }
 
     fn get_name(&self) -> String {
-        "MCTS".to_owned()
+        let selection = if self.tree_params.use_puct {
+            "PUCT"
+        } else {
+            "MCTS"
+        };
+        match self.tree_params.rave_equivalence {
+            Some(_) => format!("{}Rave", selection),
+            None => selection.to_owned(),
+        }
+    }
+
+    fn get_search_stats(&self) -> Option<String> {
+        let tree = self.tree.lock().unwrap();
+        let (winning_moves, losing_moves) = tree.count_proven_children(ROOT);
+        Some(format!(
+            "root visits: {}, reused from the last move: {}, tree nodes: {}, tree memory: {} KB, proven winning moves: {}, proven losing moves: {}",
+            tree.get_node(ROOT).get_n_val(),
+            self.reused_visits,
+            tree.num_expanded_nodes(),
+            tree.memory_usage() / 1024,
+            winning_moves,
+            losing_moves
+        ))
+    }
+
+    fn set_info_callback(&mut self, callback: InfoCallback) {
+        self.info_callback = Some(callback);
+    }
+
+    fn get_search_info(&self) -> Option<SearchInfo> {
+        self.info.clone()
+    }
+
+    fn get_search_trace(&self, max_depth: usize, min_visits: u32) -> Option<SearchTrace> {
+        Some(self.tree.lock().unwrap().export(max_depth, min_visits))
+    }
+
+    /// Grows the tree of the position after the own move silently, so the subtrees of all
+    /// replies are kept and the one the opponent plays is reused. Only a reused tree keeps
+    /// the work, so without tree reuse the bot does not ponder.
+    /// Pondering stops once the tree has `MAX_PONDER_TREE_NODES` expanded nodes.
+    fn ponder(&mut self, board: &Board, limits: &SearchLimits) {
+        if !self.reuse_tree {
+            return;
+        }
+        let info = self.info.take();
+        if !self.advance_to(board) {
+            self.reset_to(self.exploration_param, board);
+        }
+
+        let tree_nodes = self.tree.get_mut().unwrap().num_expanded_nodes();
+        let ponder_limits = SearchLimits {
+            nodes: Some(MAX_PONDER_TREE_NODES.saturating_sub(tree_nodes)),
+            ..limits.clone()
+        };
+        let callback = self.info_callback.take();
+        self.grow(&ponder_limits);
+        self.info_callback = callback;
+        self.info = info;
+    }
+}
+
+/// returns the lower confidence bound of the mean of a child, used by the secure child selection
+fn secure_value(child: &ChildStats) -> f64 {
+    child.mean - SECURE_CONFIDENCE / (child.visits as f64).sqrt()
+}
+
+/// returns the outcome of a rollout from the view of the player that moved into the board
+fn outcome_for_mover(board: &Board, eval: f64) -> f64 {
+    match board.get_player() {
+        PieceColor::Attacker => -eval,
+        PieceColor::Defender => eval,
+    }
+}
+
+/// returns the current search information of the tree.
+/// The score is the mean outcome of the most visited move of the root.
+fn search_info(tree: &MctsTree, nodes: usize, start_time: &time::Instant) -> SearchInfo {
+    // the stats guard against a child without visits early in the search
+    let score = tree
+        .most_visited_child(ROOT)
+        .and_then(|child| tree.get_node(child).get_stats())
+        .map_or(0.0, |stats| stats.mean);
+    let pv = tree.principal_variation();
+
+    SearchInfo {
+        depth: pv.len(),
+        score,
+        pv,
+        nodes,
+        time_ms: start_time.elapsed().as_millis(),
     }
 }
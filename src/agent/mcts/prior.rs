@@ -0,0 +1,154 @@
+use crate::{
+    eval::{
+        move_heuristic::{blocking_fields, heuristic_weight},
+        Eval,
+    },
+    game::{board::Board, piece::PieceColor, r#move::Move},
+};
+
+/// Gives the legal moves of a position prior probabilities for PUCT
+pub trait Prior {
+    /// Returns the prior probabilities of the moves in the given order. They sum up to one.
+    fn get_priors(&self, board: &Board, moves: &[Move]) -> Vec<f64>;
+}
+
+/// Prior from simple tafl heuristics: captures, king moves towards the corners
+/// and attacker moves that block the way of the king
+pub struct HeuristicPrior;
+
+/// Prior from the evaluation of the positions after the moves, e.g. by a network.
+/// The evaluations are turned into probabilities by a softmax.
+pub struct EvalPrior<T: Eval> {
+    eval_fn: T,
+    temperature: f64,
+}
+
+/// Prior from an external policy, e.g. the policy head of a network, that returns
+/// a probability for every move of a position. Moves the policy does not return get none,
+/// and the probabilities are scaled to sum up to one over the legal moves.
+/// The CLI has no policy network yet, so it is not chosen there.
+#[allow(unused)]
+pub struct PolicyPrior<F: Fn(&Board) -> Vec<(Move, f64)>> {
+    policy: F,
+}
+
+impl Prior for HeuristicPrior {
+    fn get_priors(&self, board: &Board, moves: &[Move]) -> Vec<f64> {
+        let blocking_fields = blocking_fields(board);
+        let weights: Vec<f64> = moves
+            .iter()
+            .map(|mov| heuristic_weight(board, mov, &blocking_fields))
+            .collect();
+        normalize(weights)
+    }
+}
+
+impl<T: Eval> EvalPrior<T> {
+    /// Create a prior from the evaluation.
+    /// A higher temperature makes the probabilities more uniform.
+    pub fn new(eval_fn: T, temperature: f64) -> EvalPrior<T> {
+        EvalPrior {
+            eval_fn,
+            temperature,
+        }
+    }
+}
+
+impl<T: Eval> Prior for EvalPrior<T> {
+    fn get_priors(&self, board: &Board, moves: &[Move]) -> Vec<f64> {
+        // evaluations from the view of the player to move
+        let player_factor = match board.get_player() {
+            PieceColor::Attacker => 1.0,
+            PieceColor::Defender => -1.0,
+        };
+        let values: Vec<f64> = moves
+            .iter()
+            .map(|mov| {
+                let mut next_board = board.clone();
+                next_board.make_move_captured_positions(mov);
+                player_factor * self.eval_fn.get_eval(&next_board) / self.temperature
+            })
+            .collect();
+
+        // subtract the maximum so the exponentials can not overflow
+        let max_value = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        normalize(
+            values
+                .into_iter()
+                .map(|value| (value - max_value).exp())
+                .collect(),
+        )
+    }
+}
+
+#[allow(unused)]
+impl<F: Fn(&Board) -> Vec<(Move, f64)>> PolicyPrior<F> {
+    /// Create a prior from the policy
+    pub fn new(policy: F) -> PolicyPrior<F> {
+        PolicyPrior { policy }
+    }
+}
+
+impl<F: Fn(&Board) -> Vec<(Move, f64)>> Prior for PolicyPrior<F> {
+    fn get_priors(&self, board: &Board, moves: &[Move]) -> Vec<f64> {
+        let probabilities = (self.policy)(board);
+        normalize(
+            moves
+                .iter()
+                .map(|mov| {
+                    probabilities
+                        .iter()
+                        .find(|(policy_mov, _)| policy_mov == mov)
+                        .map_or(0.0, |(_, probability)| probability.max(0.0))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// scales the weights so they sum up to one
+fn normalize(weights: Vec<f64>) -> Vec<f64> {
+    let sum: f64 = weights.iter().sum();
+    if sum <= 0.0 {
+        let uniform = 1.0 / weights.len().max(1) as f64;
+        return weights.iter().map(|_| uniform).collect();
+    }
+    weights.into_iter().map(|weight| weight / sum).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_prior_follows_the_policy_over_the_legal_moves() {
+        let board = Board::new();
+        let moves = board.get_legal_moves();
+        let (first, second) = (moves[0].clone(), moves[1].clone());
+        let illegal = Move::new(first.get_end_pos().clone(), first.get_start_pos().clone());
+        let prior = PolicyPrior::new(move |_: &Board| {
+            vec![
+                (first.clone(), 0.3),
+                (second.clone(), 0.1),
+                (illegal.clone(), 0.6),
+            ]
+        });
+
+        let priors = prior.get_priors(&board, &moves);
+        assert!((priors[0] - 0.75).abs() < 1e-12);
+        assert!((priors[1] - 0.25).abs() < 1e-12);
+        assert!(priors[2..].iter().all(|&prior| prior == 0.0));
+    }
+
+    #[test]
+    fn policy_prior_without_probabilities_is_uniform() {
+        let board = Board::new();
+        let moves = board.get_legal_moves();
+        let prior = PolicyPrior::new(|_: &Board| vec![]);
+
+        let priors = prior.get_priors(&board, &moves);
+        assert!(priors
+            .iter()
+            .all(|&prior| (prior - 1.0 / moves.len() as f64).abs() < 1e-12));
+    }
+}
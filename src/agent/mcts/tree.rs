@@ -0,0 +1,629 @@
+use fixedbitset::FixedBitSet;
+use rand::seq::SliceRandom;
+
+use std::fmt::Display;
+use std::mem;
+use std::ops::Range;
+
+use crate::agent::trace::{SearchTrace, TraceBound, TraceNode};
+use crate::game::board::{Board, GameState, BOARDSIZE};
+use crate::game::piece::{Piece, PieceColor};
+use crate::game::r#move::Move;
+use crate::utils::rng;
+
+use super::node::{ChildStats, MctsNode, NodeId, Proven};
+use super::prior::Prior;
+
+/// index of the root in the arena
+pub const ROOT: NodeId = 0;
+
+/// number of fields of the board
+const NUM_FIELDS: usize = BOARDSIZE * BOARDSIZE;
+
+/// Settings that change how the tree is searched
+#[derive(Clone, Copy)]
+pub struct TreeParams {
+    pub(crate) use_solver: bool,
+    /// visits after which RAVE and UCT are weighted equally, None for plain UCT
+    pub(crate) rave_equivalence: Option<f64>,
+    /// select by PUCT with the priors of the moves instead of UCB1
+    pub(crate) use_puct: bool,
+}
+
+/// MCTS tree whose nodes are stored in one arena.
+/// All children of a node are allocated at once, so they lie next to each other.
+/// With the solver, proven wins and losses are propagated up the tree.
+pub struct MctsTree {
+    board: Board,
+    nodes: Vec<MctsNode>,
+    params: TreeParams,
+}
+
+impl MctsTree {
+    /// Creates a tree that only consists of the root for the given board
+    pub fn new(board: Board, params: TreeParams) -> MctsTree {
+        let mut root = MctsNode::new(None);
+        root.set_terminal(board.is_game_over());
+        if params.use_solver {
+            root.set_proven(proven_for_mover(&board));
+        }
+
+        MctsTree {
+            board,
+            nodes: vec![root],
+            params,
+        }
+    }
+
+    /// Get the board of the root
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_node(&self, id: NodeId) -> &MctsNode {
+        &self.nodes[id as usize]
+    }
+
+    fn get_node_mut(&mut self, id: NodeId) -> &mut MctsNode {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Get the ids of all allocated children of the node
+    pub fn children(&self, id: NodeId) -> Range<NodeId> {
+        let node = self.get_node(id);
+        if !node.has_children() {
+            return 0..0;
+        }
+        node.get_first_child()..node.get_first_child() + node.num_children()
+    }
+
+    /// Get the ids of the children of the node that have been expanded
+    pub fn expanded_children(&self, id: NodeId) -> Range<NodeId> {
+        let node = self.get_node(id);
+        if !node.has_children() {
+            return 0..0;
+        }
+        node.get_first_child()..node.get_first_child() + node.num_expanded()
+    }
+
+    /// Get the number of nodes that have been expanded
+    pub fn num_expanded_nodes(&self) -> usize {
+        1 + self
+            .nodes
+            .iter()
+            .map(|node| node.num_expanded() as usize)
+            .sum::<usize>()
+    }
+
+    /// Get the number of bytes used by the tree
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<MctsTree>() + self.nodes.capacity() * mem::size_of::<MctsNode>()
+    }
+
+    /// Descends from the root by UCB until it reaches a node that is terminal or has unexplored children.
+    /// With PUCT, unexplored children compete with the explored ones and the descent stops
+    /// when an unexplored child is chosen. It is moved to the front of the unexplored children,
+    /// so it is expanded next.
+    /// The path is written to `path`, every node on it gets a virtual loss and the board follows the path.
+    /// Returns false if no node can be expanded anymore or the root has been proven.
+    pub fn select(&mut self, expl_param: f64, path: &mut Vec<NodeId>, board: &mut Board) -> bool {
+        path.clear();
+        if self.get_node(ROOT).get_proven() != Proven::Unknown {
+            return false;
+        }
+        let mut current = ROOT;
+
+        loop {
+            self.get_node_mut(current).add_virtual_loss();
+            path.push(current);
+
+            let node = self.get_node(current);
+            if node.is_terminal()
+                || !node.has_children()
+                || (!self.params.use_puct && !node.is_fully_expanded())
+            {
+                return true;
+            }
+
+            match self.get_next_child(current, expl_param) {
+                Some(child) if child >= self.expanded_children(current).end => {
+                    let next_unexplored = self.expanded_children(current).end;
+                    self.nodes.swap(child as usize, next_unexplored as usize);
+                    return true;
+                }
+                Some(child) => {
+                    board.make_move_captured_positions(
+                        self.get_node(child).get_mov().as_ref().unwrap(),
+                    );
+                    current = child;
+                }
+                None => {
+                    self.revert_virtual_loss(path);
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Returns the child with the highest UCB1 value or PUCT value.
+    /// UCB1 only looks at expanded children, PUCT at all of them.
+    /// Children that are proven losses are never chosen.
+    fn get_next_child(&self, id: NodeId, expl_param: f64) -> Option<NodeId> {
+        let node = self.get_node(id);
+        let parent_n_val = (node.get_n_val() + node.get_virtual_loss()) as f64;
+        let candidates = if self.params.use_puct {
+            self.children(id)
+        } else {
+            self.expanded_children(id)
+        };
+
+        let mut maxval = f64::NEG_INFINITY;
+        let mut maxchild = None;
+
+        for child in candidates {
+            let child_node = self.get_node(child);
+            if child_node.get_proven() == Proven::Loss {
+                continue;
+            }
+
+            let child_val = if self.params.use_puct {
+                child_node.compute_puct_val(expl_param, parent_n_val, self.params.rave_equivalence)
+            } else {
+                child_node.compute_ucb_val(expl_param, parent_n_val, self.params.rave_equivalence)
+            };
+            if child_val > maxval {
+                maxval = child_val;
+                maxchild = Some(child);
+            }
+        }
+
+        maxchild
+    }
+
+    /// Expands the next unexplored child of the node and makes its move on the board.
+    /// The children of the node are allocated the first time it is expanded.
+    /// With a prior, they get their prior probabilities and are expanded in the order of them.
+    /// The new child gets a virtual loss like the nodes of the selected path.
+    /// Returns None if all children have already been expanded.
+    pub fn expand(
+        &mut self,
+        id: NodeId,
+        board: &mut Board,
+        prior: Option<&dyn Prior>,
+    ) -> Option<NodeId> {
+        if !self.get_node(id).has_children() {
+            // shuffle the moves to get better results
+            let mut moves = board.get_legal_moves();
+            rng::with_rng(|rng| moves.shuffle(rng));
+
+            let mut children: Vec<(Move, f64)> = match prior {
+                Some(prior) => {
+                    let priors = prior.get_priors(board, &moves);
+                    moves.into_iter().zip(priors).collect()
+                }
+                None => {
+                    let uniform = 1.0 / moves.len().max(1) as f64;
+                    moves.into_iter().map(|mov| (mov, uniform)).collect()
+                }
+            };
+            children.sort_by(|(_, prior_a), (_, prior_b)| prior_b.total_cmp(prior_a));
+
+            let first_child = self.nodes.len() as NodeId;
+            let num_children = children.len() as u32;
+            self.nodes.extend(children.into_iter().map(|(mov, prior)| {
+                let mut child = MctsNode::new(Some(mov));
+                child.set_prior(prior);
+                child
+            }));
+            self.get_node_mut(id)
+                .set_children(first_child, num_children, 0);
+        }
+
+        let node = self.get_node(id);
+        if node.num_expanded() == node.num_children() {
+            return None;
+        }
+        let child = node.get_first_child() + node.num_expanded();
+        self.get_node_mut(id).expand_next();
+
+        board.make_move_captured_positions(self.get_node(child).get_mov().as_ref().unwrap());
+        let use_solver = self.params.use_solver;
+        let child_node = self.get_node_mut(child);
+        child_node.set_terminal(board.is_game_over());
+        if use_solver {
+            child_node.set_proven(proven_for_mover(board));
+        }
+        child_node.add_virtual_loss();
+        Some(child)
+    }
+
+    /// performs back propagation along the path switching the sign in each layer
+    /// to accomodate for a two-player-game.
+    /// The outcome is from the view of the player that moved into the last node of the path.
+    /// The virtual loss of the search is removed on the way.
+    /// With RAVE, the moves of the playout after the last node update the all-moves-as-first statistics
+    /// and with the solver, the proven values are updated afterwards.
+    pub fn backup(&mut self, path: &[NodeId], leaf_outcome: f64, playout: &[Move]) {
+        let mut outcome = leaf_outcome;
+
+        for &id in path.iter().rev() {
+            let node = self.get_node_mut(id);
+            node.update(outcome);
+            node.remove_virtual_loss();
+            outcome = -outcome;
+        }
+
+        if self.params.rave_equivalence.is_some() {
+            self.update_amaf(path, leaf_outcome, playout);
+        }
+        if self.params.use_solver {
+            self.propagate_proven(path);
+        }
+    }
+
+    /// updates the all-moves-as-first statistics of the children of every node on the path.
+    /// A child gets the outcome if its move was played later in the simulation by the player to move at the node.
+    /// The outcome is from the view of the player that moved into the last node of the path.
+    fn update_amaf(&mut self, path: &[NodeId], leaf_outcome: f64, playout: &[Move]) {
+        // the moves of the simulation are numbered from the root on, so the player to move
+        // at the node with index k of the path makes the moves with the same parity as k
+        let mut played = [
+            FixedBitSet::with_capacity(NUM_FIELDS * NUM_FIELDS),
+            FixedBitSet::with_capacity(NUM_FIELDS * NUM_FIELDS),
+        ];
+        let leaf_idx = path.len() - 1;
+        for (idx, mov) in playout.iter().enumerate() {
+            played[(leaf_idx + idx) % 2].insert(move_key(mov));
+        }
+
+        // the outcome from the view of the player that moved into the children of the leaf
+        let mut child_outcome = -leaf_outcome;
+        for idx in (0..path.len()).rev() {
+            if idx < leaf_idx {
+                let mov = self.get_node(path[idx + 1]).get_mov().as_ref().unwrap();
+                played[idx % 2].insert(move_key(mov));
+            }
+
+            for child in self.children(path[idx]) {
+                let child_node = self.get_node_mut(child);
+                if played[idx % 2].contains(move_key(child_node.get_mov().as_ref().unwrap())) {
+                    child_node.update_amaf(child_outcome);
+                }
+            }
+            child_outcome = -child_outcome;
+        }
+    }
+
+    /// proves the ancestors of the last node of the path as far as possible
+    fn propagate_proven(&mut self, path: &[NodeId]) {
+        for &id in path.iter().rev().skip(1) {
+            let proven = self.solve(id);
+            if proven == Proven::Unknown {
+                break;
+            }
+            self.get_node_mut(id).set_proven(proven);
+        }
+    }
+
+    /// computes the proven value of a node from its children.
+    /// The player who moved into the node has lost if the opponent has a winning move
+    /// and has won if all moves of the opponent lose.
+    fn solve(&self, id: NodeId) -> Proven {
+        let node = self.get_node(id);
+        if node.get_proven() != Proven::Unknown || node.num_children() == 0 {
+            return node.get_proven();
+        }
+
+        let mut all_lost = node.is_fully_expanded();
+        for child in self.expanded_children(id) {
+            match self.get_node(child).get_proven() {
+                Proven::Win => return Proven::Loss,
+                Proven::Loss => {}
+                Proven::Unknown => all_lost = false,
+            }
+        }
+
+        if all_lost {
+            Proven::Win
+        } else {
+            Proven::Unknown
+        }
+    }
+
+    /// removes the virtual loss of a search from all nodes of the path
+    pub fn revert_virtual_loss(&mut self, path: &[NodeId]) {
+        for &id in path {
+            self.get_node_mut(id).remove_virtual_loss();
+        }
+    }
+
+    /// returns the expanded child of the node that was visited most often.
+    /// A proven win is always preferred.
+    pub fn most_visited_child(&self, id: NodeId) -> Option<NodeId> {
+        self.proven_win(id).or_else(|| {
+            self.expanded_children(id)
+                .max_by_key(|&child| self.get_node(child).get_n_val())
+        })
+    }
+
+    /// returns the statistics of the expanded children of the node
+    pub fn child_stats(&self, id: NodeId) -> Vec<ChildStats> {
+        self.expanded_children(id)
+            .filter_map(|child| self.get_node(child).get_stats())
+            .collect()
+    }
+
+    /// returns a child of the node that is a proven win for the player to move
+    pub fn proven_win(&self, id: NodeId) -> Option<NodeId> {
+        self.expanded_children(id)
+            .find(|&child| self.get_node(child).get_proven() == Proven::Win)
+    }
+
+    /// counts the expanded children of the node that are proven wins and losses
+    /// for the player to move
+    pub fn count_proven_children(&self, id: NodeId) -> (usize, usize) {
+        self.expanded_children(id)
+            .fold((0, 0), |(wins, losses), child| {
+                match self.get_node(child).get_proven() {
+                    Proven::Win => (wins + 1, losses),
+                    Proven::Loss => (wins, losses + 1),
+                    Proven::Unknown => (wins, losses),
+                }
+            })
+    }
+
+    /// returns the tree down to the given depth without the nodes with fewer visits
+    pub fn export(&self, max_depth: usize, min_visits: u32) -> SearchTrace {
+        let mut trace = SearchTrace::new(self.trace_node(ROOT));
+        self.export_children(ROOT, &mut trace, 0, max_depth, min_visits);
+        trace
+    }
+
+    /// adds the expanded children of the node that are within the limits to the trace
+    fn export_children(
+        &self,
+        id: NodeId,
+        trace: &mut SearchTrace,
+        trace_id: usize,
+        depth: usize,
+        min_visits: u32,
+    ) {
+        if depth == 0 {
+            return;
+        }
+        for child in self.expanded_children(id) {
+            if self.get_node(child).get_n_val() < min_visits {
+                continue;
+            }
+            let child_trace_id = trace.add_child(trace_id, self.trace_node(child));
+            self.export_children(child, trace, child_trace_id, depth - 1, min_visits);
+        }
+    }
+
+    /// returns the statistics of the node for the export, proven nodes are marked by their bound
+    fn trace_node(&self, id: NodeId) -> TraceNode {
+        let node = self.get_node(id);
+        let mut trace_node = TraceNode::new(node.get_mov().clone());
+        trace_node.visits = Some(node.get_n_val());
+        trace_node.score = Some(node.get_q_val() / node.get_n_val().max(1) as f64);
+        trace_node.bound = match node.get_proven() {
+            Proven::Win => Some(TraceBound::ProvenWin),
+            Proven::Loss => Some(TraceBound::ProvenLoss),
+            Proven::Unknown => None,
+        };
+        trace_node
+    }
+
+    /// returns the most visited path through the tree
+    pub fn principal_variation(&self) -> Vec<Move> {
+        let mut pv = vec![];
+        let mut current = ROOT;
+
+        while let Some(child) = self.most_visited_child(current) {
+            pv.push(self.get_node(child).get_mov().as_ref().unwrap().clone());
+            current = child;
+        }
+
+        pv
+    }
+
+    /// returns the node of the board if it is at most `depth` plies below the root
+    pub fn find_descendant(&self, board: &Board, depth: usize) -> Option<NodeId> {
+        self.find_below(ROOT, &self.board, board, depth)
+    }
+
+    /// returns the node of the board if it is at most `depth` plies below the given node
+    fn find_below(
+        &self,
+        id: NodeId,
+        node_board: &Board,
+        board: &Board,
+        depth: usize,
+    ) -> Option<NodeId> {
+        if node_board == board {
+            return Some(id);
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        self.expanded_children(id).find_map(|child| {
+            let mut child_board = node_board.clone();
+            child_board
+                .make_move_captured_positions(self.get_node(child).get_mov().as_ref().unwrap());
+            self.find_below(child, &child_board, board, depth - 1)
+        })
+    }
+
+    /// Returns a new tree with the given node as root.
+    /// Only the subtree of the node is copied, so the rest of the arena is freed with the old tree.
+    pub fn subtree(&self, id: NodeId, board: Board) -> MctsTree {
+        let mut root = self.get_node(id).clone();
+        root.make_root();
+        root.clear_children();
+
+        let mut tree = MctsTree {
+            board,
+            nodes: vec![root],
+            params: self.params,
+        };
+        tree.copy_children(ROOT, self, id);
+        tree
+    }
+
+    /// Adds the search of another tree of the same position to the tree.
+    /// The statistics of root children expanded in both trees are added,
+    /// children only expanded in the other tree are copied with their subtrees.
+    pub fn merge(&mut self, other: &MctsTree) {
+        self.get_node_mut(ROOT).add_stats(other.get_node(ROOT));
+
+        if !self.get_node(ROOT).has_children() {
+            self.copy_children(ROOT, other, ROOT);
+        } else {
+            for other_child in other.expanded_children(ROOT) {
+                let mov = other.get_node(other_child).get_mov();
+                let Some(own_child) = self
+                    .children(ROOT)
+                    .find(|&child| self.get_node(child).get_mov() == mov)
+                else {
+                    continue;
+                };
+
+                let explored_end = self.expanded_children(ROOT).end;
+                if own_child < explored_end {
+                    let other_node = other.get_node(other_child);
+                    let own_node = self.get_node_mut(own_child);
+                    own_node.add_stats(other_node);
+                    if other_node.get_proven() != Proven::Unknown {
+                        own_node.set_proven(other_node.get_proven());
+                    }
+                } else {
+                    // move the child into the explored part of the children
+                    self.nodes.swap(own_child as usize, explored_end as usize);
+                    self.get_node_mut(ROOT).expand_next();
+
+                    let mut child = other.get_node(other_child).clone();
+                    child.clear_children();
+                    *self.get_node_mut(explored_end) = child;
+                    self.copy_children(explored_end, other, other_child);
+                }
+            }
+        }
+
+        if self.params.use_solver {
+            let proven = self.solve(ROOT);
+            self.get_node_mut(ROOT).set_proven(proven);
+        }
+    }
+
+    /// Copies the children of a node of another tree below the given node.
+    /// The subtrees of expanded children are copied as well.
+    fn copy_children(&mut self, to: NodeId, other: &MctsTree, from: NodeId) {
+        let mut stack = vec![(to, from)];
+
+        while let Some((to, from)) = stack.pop() {
+            let source = other.get_node(from);
+            if !source.has_children() {
+                continue;
+            }
+
+            let first_child = self.nodes.len() as NodeId;
+            for (idx, other_child) in other.children(from).enumerate() {
+                let mut child = other.get_node(other_child).clone();
+                child.clear_children();
+                self.nodes.push(child);
+
+                if (idx as u32) < source.num_expanded() {
+                    stack.push((first_child + idx as NodeId, other_child));
+                }
+            }
+            self.get_node_mut(to).set_children(
+                first_child,
+                source.num_children(),
+                source.num_expanded(),
+            );
+        }
+    }
+}
+
+/// returns a number that identifies the move
+fn move_key(mov: &Move) -> usize {
+    mov.get_start_pos().get_num() * NUM_FIELDS + mov.get_end_pos().get_num()
+}
+
+/// returns the proven value of a finished game from the view of the player who made the last move
+fn proven_for_mover(board: &Board) -> Proven {
+    let mover = board.get_player().get_opposite();
+    match (board.who_won(), mover) {
+        (GameState::WinAttacker, PieceColor::Attacker)
+        | (GameState::WinDefender, PieceColor::Defender) => Proven::Win,
+        (GameState::WinAttacker, PieceColor::Defender)
+        | (GameState::WinDefender, PieceColor::Attacker) => Proven::Loss,
+        (GameState::Draw, _) | (GameState::Undecided, _) => Proven::Unknown,
+    }
+}
+
+impl Display for MctsTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let root = self.get_node(ROOT);
+        writeln!(
+            f,
+            "color: {}, Qv: {}, nv: {}, #children: {}",
+            Piece::Pawn(self.board.get_player().clone()),
+            root.get_q_val(),
+            root.get_n_val(),
+            root.num_expanded(),
+        )?;
+        for (idx, stats) in self.child_stats(ROOT).iter().enumerate() {
+            writeln!(f, "{idx}: {}", stats)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAVE_PARAMS: TreeParams = TreeParams {
+        use_solver: false,
+        rave_equivalence: Some(100.0),
+        use_puct: false,
+    };
+
+    #[test]
+    fn amaf_of_root_only_path_is_from_view_of_player_to_move() {
+        let mut board = Board::new();
+        let mut tree = MctsTree::new(board.clone(), RAVE_PARAMS);
+        let mut path = vec![];
+        assert!(tree.select(1.0, &mut path, &mut board));
+        assert_eq!(path.len(), 1);
+        tree.expand(ROOT, &mut board.clone(), None);
+
+        let child = tree.children(ROOT).start + 1;
+        let mov = tree.get_node(child).get_mov().clone().unwrap();
+        // the outcome is a win for the defender, who moved into the root
+        tree.backup(&path, 1.0, &[mov]);
+
+        assert_eq!(tree.get_node(child).get_amaf_q_val(), -1.0);
+    }
+
+    #[test]
+    fn amaf_of_two_node_path_alternates_players() {
+        let mut board = Board::new();
+        let mut tree = MctsTree::new(board.clone(), RAVE_PARAMS);
+        let mut path = vec![];
+        assert!(tree.select(1.0, &mut path, &mut board));
+        let child = tree.expand(ROOT, &mut board, None).unwrap();
+        path.push(child);
+
+        tree.expand(child, &mut board.clone(), None);
+        let grandchild = tree.children(child).start;
+        let mov = tree.get_node(grandchild).get_mov().clone().unwrap();
+        // the outcome is a win for the attacker, who moved into the child
+        tree.backup(&path, 1.0, &[mov]);
+
+        assert_eq!(tree.get_node(child).get_amaf_q_val(), 1.0);
+        assert_eq!(tree.get_node(grandchild).get_amaf_q_val(), -1.0);
+    }
+}
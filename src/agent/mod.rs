@@ -3,9 +3,20 @@ use crate::{
     game::{board::Board, r#move::Move},
 };
 
+use self::{
+    info::{InfoCallback, SearchInfo},
+    limits::SearchLimits,
+    trace::SearchTrace,
+};
+
 pub mod alpha_beta;
+pub mod book;
+pub mod info;
+pub mod limits;
 pub mod mcts;
 pub mod random;
+pub mod time_manager;
+pub mod trace;
 
 pub trait BotInit {
     type Ev: Eval;
@@ -14,9 +25,10 @@ pub trait BotInit {
     fn new(bot_params: Self::Params, eval_fn: Self::Ev) -> Self;
 }
 
-pub trait Bot {
-    /// Get the next move for the bot
-    fn get_next_move(&mut self, board: &Board, time: u128) -> Option<Move>;
+pub trait Bot: Send {
+    /// Get the next move for the bot, searching until one of the limits is reached.
+    /// An infinite search only returns after it has been stopped.
+    fn get_next_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move>;
 
     /// Reset the bot for a new state
     fn reset(&mut self, board: &Board);
@@ -26,4 +38,38 @@ pub trait Bot {
 
     /// Get the name of the bot
     fn get_name(&self) -> String;
+
+    /// Get statistics about the last search if the bot collects any
+    fn get_search_stats(&self) -> Option<String> {
+        None
+    }
+
+    /// Set a callback that receives search information while the bot is searching
+    fn set_info_callback(&mut self, _callback: InfoCallback) {}
+
+    /// Get the latest search information if the bot reports any
+    fn get_search_info(&self) -> Option<SearchInfo> {
+        None
+    }
+
+    /// Get the tree of the last search down to the given depth without the nodes
+    /// with fewer visits, if the bot can export it
+    fn get_search_trace(&self, _max_depth: usize, _min_visits: u32) -> Option<SearchTrace> {
+        None
+    }
+
+    /// Get the reply of the opponent the bot expects after it played the move,
+    /// taken from the principal variation of its last search
+    fn get_expected_reply(&self, mov: &Move) -> Option<Move> {
+        let info = self.get_search_info()?;
+        if info.pv.first() != Some(mov) {
+            return None;
+        }
+        info.pv.get(1).cloned()
+    }
+
+    /// Search on the time of the opponent, given the position after the own move,
+    /// until the limits are stopped. A bot that keeps its work between searches uses it
+    /// for its next move and discards the work on replies the opponent did not play.
+    fn ponder(&mut self, _board: &Board, _limits: &SearchLimits) {}
 }
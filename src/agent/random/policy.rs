@@ -1,5 +1,5 @@
 use crate::{
-    agent::{Bot, BotInit},
+    agent::{limits::SearchLimits, Bot, BotInit},
     eval::Eval,
     game::{board::Board, r#move::Move},
 };
@@ -17,9 +17,11 @@ impl<T: Eval> BotInit for RandomBot<T> {
     }
 }
 
-impl<T: Eval> Bot for RandomBot<T> {
-    /// returns a random move
-    fn get_next_move(&mut self, board: &Board, _time: u128) -> Option<Move> {
+impl<T: Eval + Send> Bot for RandomBot<T> {
+    /// returns a random move, an infinite search is held back until it is stopped
+    fn get_next_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        limits.wait_for_stop();
+        limits.seed_rng(0);
         board.get_random_move()
     }
 
@@ -0,0 +1,88 @@
+use std::time;
+
+use crate::game::{board::Board, piece::PieceColor, r#move::Move};
+
+use super::limits::SearchLimits;
+
+/// moves that are expected to be left at least, even with few pieces on the board
+const MIN_MOVES_TO_GO: f64 = 10.0;
+/// moves that are expected to be left for every piece on the board
+const MOVES_TO_GO_PER_PIECE: f64 = 1.0;
+/// share of the increment that is spent on the move
+const INCREMENT_SHARE: f64 = 0.8;
+/// time in ms kept back for making the move after the search
+const SAFETY_MARGIN_MS: u128 = 20;
+/// the hard limit is this many times the planned time of the move
+const HARD_FACTOR: f64 = 4.0;
+/// share of the remaining time a single move may use at most
+const MAX_SHARE: f64 = 0.5;
+/// scale of the planned time right after the best move changed
+const UNSTABLE_SCALE: f64 = 1.5;
+/// scale of the planned time once the best move has been stable for long
+const STABLE_SCALE: f64 = 0.5;
+/// the scale shrinks by this much for every planned time in which the best move stayed the same
+const STABILITY_RATE: f64 = 1.0;
+
+/// Decides how much of the time on the clock a bot spends on a move.
+/// The planned time depends on the game phase, estimated by the pieces on the board,
+/// and is stretched while the best move keeps changing and shrunk once it is stable.
+pub struct TimeManager {
+    start_time: time::Instant,
+    /// planned time of the move in ms, None if the time is fixed by the limits
+    soft_ms: Option<u128>,
+    best_move: Option<Move>,
+    /// elapsed time in ms when the best move changed last
+    changed_ms: u128,
+}
+
+impl TimeManager {
+    /// Plans the time of the move from the clock in the limits.
+    /// Returns the manager and limits whose movetime is the most the move may take.
+    /// Without a clock the limits are returned as they are and only they end the search.
+    pub fn new(limits: &SearchLimits, board: &Board) -> (TimeManager, SearchLimits) {
+        let mut manager = TimeManager {
+            start_time: time::Instant::now(),
+            soft_ms: None,
+            best_move: None,
+            changed_ms: 0,
+        };
+        let Some(clock) = limits.clock else {
+            return (manager, limits.clone());
+        };
+
+        let pieces = board.number_of_colored_pieces(&PieceColor::Attacker)
+            + board.number_of_colored_pieces(&PieceColor::Defender);
+        let moves_to_go = MIN_MOVES_TO_GO + MOVES_TO_GO_PER_PIECE * pieces as f64;
+        let available = clock.remaining_ms.saturating_sub(SAFETY_MARGIN_MS) as f64;
+
+        let planned = available / moves_to_go + INCREMENT_SHARE * clock.increment_ms as f64;
+        let mut hard = (HARD_FACTOR * planned).min(MAX_SHARE * available) as u128;
+        if let Some(movetime) = limits.movetime {
+            hard = hard.min(movetime);
+        }
+        manager.soft_ms = Some((planned as u128).min(hard));
+
+        let mut planned_limits = limits.clone();
+        planned_limits.movetime = Some(hard);
+        (manager, planned_limits)
+    }
+
+    /// Called during the search with its current best move.
+    /// Returns true if the search should stop because the planned time is used up.
+    /// The stability of the best move is measured in time, so it does not depend on how often this is called.
+    pub fn should_stop(&mut self, best_move: Option<&Move>) -> bool {
+        let Some(soft_ms) = self.soft_ms else {
+            return false;
+        };
+
+        let elapsed = self.start_time.elapsed().as_millis();
+        if best_move != self.best_move.as_ref() {
+            self.best_move = best_move.cloned();
+            self.changed_ms = elapsed;
+        }
+
+        let stable_share = (elapsed - self.changed_ms) as f64 / soft_ms.max(1) as f64;
+        let scale = (UNSTABLE_SCALE - STABILITY_RATE * stable_share).max(STABLE_SCALE);
+        elapsed as f64 >= scale * soft_ms as f64
+    }
+}
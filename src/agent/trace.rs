@@ -0,0 +1,234 @@
+use std::{fmt::Display, fs, io, path::Path};
+
+use crate::game::r#move::Move;
+
+/// How the score of an exported node relates to its true value
+#[derive(Clone, Copy, PartialEq)]
+pub enum TraceBound {
+    Exact,
+    /// the node failed high, its value is at least the score
+    Lower,
+    /// the node failed low, its value is at most the score
+    Upper,
+    ProvenWin,
+    ProvenLoss,
+}
+
+/// A node of an exported search tree
+#[derive(Clone)]
+pub struct TraceNode {
+    /// move that leads to the node, None for the root and null moves
+    pub mov: Option<Move>,
+    /// number of visits in MCTS
+    pub visits: Option<u32>,
+    /// mean outcome for the player who moved into the node in MCTS,
+    /// negamax score for the player to move in alpha-beta
+    pub score: Option<f64>,
+    /// alpha and beta the node was searched with
+    pub window: Option<(f64, f64)>,
+    pub bound: Option<TraceBound>,
+    /// the node was not searched because of a cutoff
+    pub pruned: bool,
+    children: Vec<usize>,
+}
+
+/// A search tree exported to inspect why a bot chose its move.
+/// The root is the first node.
+#[derive(Clone)]
+pub struct SearchTrace {
+    nodes: Vec<TraceNode>,
+}
+
+impl Display for TraceBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceBound::Exact => write!(f, "exact"),
+            TraceBound::Lower => write!(f, "lower"),
+            TraceBound::Upper => write!(f, "upper"),
+            TraceBound::ProvenWin => write!(f, "proven win"),
+            TraceBound::ProvenLoss => write!(f, "proven loss"),
+        }
+    }
+}
+
+impl TraceNode {
+    /// Creates a node without any statistics
+    pub fn new(mov: Option<Move>) -> TraceNode {
+        TraceNode {
+            mov,
+            visits: None,
+            score: None,
+            window: None,
+            bound: None,
+            pruned: false,
+            children: vec![],
+        }
+    }
+
+    /// returns the lines of the label of the node, the root and null moves have no move
+    fn label_lines(&self, is_root: bool) -> Vec<String> {
+        let mut lines = vec![match (&self.mov, is_root) {
+            (Some(mov), _) => mov.to_string(),
+            (None, true) => "root".to_string(),
+            (None, false) => "null move".to_string(),
+        }];
+        if let Some(visits) = self.visits {
+            lines.push(format!("visits {}", visits));
+        }
+        if let Some(score) = self.score {
+            lines.push(format!("score {:.3}", score));
+        }
+        if let Some((alpha, beta)) = self.window {
+            lines.push(format!("window [{:.3}, {:.3}]", alpha, beta));
+        }
+        if let Some(bound) = self.bound {
+            lines.push(bound.to_string());
+        }
+        if self.pruned {
+            lines.push("pruned".to_string());
+        }
+        lines
+    }
+}
+
+impl SearchTrace {
+    /// Creates a trace that only consists of the root
+    pub fn new(root: TraceNode) -> SearchTrace {
+        SearchTrace { nodes: vec![root] }
+    }
+
+    /// Adds a child to the node and returns its id
+    pub fn add_child(&mut self, parent: usize, node: TraceNode) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    /// Returns the id of the child of the node that is reached by the move
+    pub fn find_child(&self, parent: usize, mov: &Option<Move>) -> Option<usize> {
+        self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .find(|&id| self.nodes[id].mov == *mov)
+    }
+
+    pub fn get_node_mut(&mut self, id: usize) -> &mut TraceNode {
+        &mut self.nodes[id]
+    }
+
+    /// returns a copy that only keeps the nodes down to the given depth
+    /// and with at least the given visits. Nodes without visit counts are kept.
+    pub fn limited(&self, max_depth: usize, min_visits: u32) -> SearchTrace {
+        let mut limited = SearchTrace::new(self.nodes[0].clone());
+        limited.nodes[0].children.clear();
+        self.copy_children(0, &mut limited, 0, max_depth, min_visits);
+        limited
+    }
+
+    /// copies the children of a node that are within the limits below the node of the copy
+    fn copy_children(
+        &self,
+        id: usize,
+        limited: &mut SearchTrace,
+        limited_id: usize,
+        depth: usize,
+        min_visits: u32,
+    ) {
+        if depth == 0 {
+            return;
+        }
+        for &child in self.nodes[id].children.iter() {
+            let node = &self.nodes[child];
+            if node.visits.is_some_and(|visits| visits < min_visits) {
+                continue;
+            }
+            let mut copy = node.clone();
+            copy.children.clear();
+            let copy_id = limited.add_child(limited_id, copy);
+            self.copy_children(child, limited, copy_id, depth - 1, min_visits);
+        }
+    }
+
+    /// Returns the tree in the DOT language of Graphviz.
+    /// Pruned nodes are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let label = node.label_lines(id == 0).join("\\n");
+            let style = if node.pruned {
+                ", style=dashed, color=gray"
+            } else {
+                ""
+            };
+            dot.push_str(&format!("    n{} [label=\"{}\"{}];\n", id, label, style));
+            for child in node.children.iter() {
+                dot.push_str(&format!("    n{} -> n{};\n", id, child));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the tree as nested JSON objects.
+    /// Infinite scores and bounds of the window are written as null.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(0, &mut json);
+        json
+    }
+
+    /// appends the node and its subtree as JSON object
+    fn write_json(&self, id: usize, json: &mut String) {
+        let node = &self.nodes[id];
+        let mov = match &node.mov {
+            Some(mov) => format!("\"{}\"", mov),
+            None => "null".to_string(),
+        };
+        let (alpha, beta) = match node.window {
+            Some((alpha, beta)) => (json_number(Some(alpha)), json_number(Some(beta))),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let bound = match node.bound {
+            Some(bound) => format!("\"{}\"", bound),
+            None => "null".to_string(),
+        };
+        let visits = match node.visits {
+            Some(visits) => visits.to_string(),
+            None => "null".to_string(),
+        };
+
+        json.push_str(&format!(
+            "{{\"move\":{},\"visits\":{},\"score\":{},\"alpha\":{},\"beta\":{},\"bound\":{},\"pruned\":{},\"children\":[",
+            mov,
+            visits,
+            json_number(node.score),
+            alpha,
+            beta,
+            bound,
+            node.pruned
+        ));
+        for (idx, &child) in node.children.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            self.write_json(child, json);
+        }
+        json.push_str("]}");
+    }
+
+    /// Writes the tree to `<path>.dot` and `<path>.json`
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path.with_extension("dot"), self.to_dot())?;
+        fs::write(path.with_extension("json"), self.to_json())
+    }
+}
+
+/// returns the number as JSON, null if it is missing or not finite
+fn json_number(number: Option<f64>) -> String {
+    match number {
+        Some(number) if number.is_finite() => number.to_string(),
+        _ => "null".to_string(),
+    }
+}
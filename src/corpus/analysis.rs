@@ -0,0 +1,261 @@
+use std::{fmt::Display, fs, path::Path};
+
+use crate::game::{
+    board::{GameState, BOARDSIZE},
+    position::Position,
+    r#move::Move,
+};
+
+use super::record::GameRecord;
+
+/// Counts per square of the board
+pub struct Heatmap {
+    counts: Vec<usize>,
+}
+
+/// Statistics over a corpus of games
+pub struct CorpusAnalysis {
+    capture_heatmap: Heatmap,
+    king_escape_heatmap: Heatmap,
+    captures_by_ply: Vec<usize>,
+    legal_moves_by_ply: Vec<usize>,
+    positions_by_ply: Vec<usize>,
+    game_lengths: Vec<usize>,
+}
+
+impl Heatmap {
+    /// Create a heatmap with all counts set to zero
+    pub fn new() -> Heatmap {
+        Heatmap {
+            counts: vec![0; BOARDSIZE * BOARDSIZE],
+        }
+    }
+
+    /// Increments the count of the position
+    pub fn add(&mut self, pos: &Position) {
+        self.counts[pos.get_num()] += 1;
+    }
+
+    /// Get the count of the position
+    pub fn get(&self, pos: &Position) -> usize {
+        self.counts[pos.get_num()]
+    }
+
+    /// Returns the heatmap as CSV with one line per row of the board
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row_idx in 0..BOARDSIZE {
+            let row: Vec<String> = (0..BOARDSIZE)
+                .map(|col| self.get(&Position::new_xy(row_idx, col)).to_string())
+                .collect();
+            csv += &format!("{}\n", row.join(","));
+        }
+        csv
+    }
+}
+
+impl Display for Heatmap {
+    /// renders the counts on the same grid as the board
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00 ")?;
+        for i in 0..BOARDSIZE {
+            write!(f, "{:>6}", (65 + i as u8) as char)?;
+        }
+        writeln!(f)?;
+        for row_idx in 0..BOARDSIZE {
+            write!(f, "{:02} ", row_idx)?;
+            for col in 0..BOARDSIZE {
+                match self.get(&Position::new_xy(row_idx, col)) {
+                    0 => write!(f, "{:>6}", "."),
+                    count => write!(f, "{:>6}", count),
+                }?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl CorpusAnalysis {
+    /// Create an empty analysis
+    pub fn new() -> CorpusAnalysis {
+        CorpusAnalysis {
+            capture_heatmap: Heatmap::new(),
+            king_escape_heatmap: Heatmap::new(),
+            captures_by_ply: vec![],
+            legal_moves_by_ply: vec![],
+            positions_by_ply: vec![],
+            game_lengths: vec![],
+        }
+    }
+
+    /// Analyses all given games
+    pub fn from_records(records: &[GameRecord]) -> CorpusAnalysis {
+        let mut analysis = CorpusAnalysis::new();
+        for record in records {
+            analysis.add_game(record);
+        }
+        analysis
+    }
+
+    /// Adds the statistics of a game to the analysis
+    pub fn add_game(&mut self, record: &GameRecord) {
+        let replay = record.replay();
+        let num_plies = replay.len() - 1;
+
+        if self.positions_by_ply.len() < num_plies + 1 {
+            self.captures_by_ply.resize(num_plies + 1, 0);
+            self.legal_moves_by_ply.resize(num_plies + 1, 0);
+            self.positions_by_ply.resize(num_plies + 1, 0);
+        }
+
+        for (ply, (board, captured)) in replay.iter().enumerate() {
+            for pos in captured {
+                self.capture_heatmap.add(pos);
+            }
+            self.captures_by_ply[ply] += captured.len();
+
+            if !board.is_game_over() {
+                self.legal_moves_by_ply[ply] += board.get_legal_moves().len();
+                self.positions_by_ply[ply] += 1;
+            }
+        }
+
+        // every square the king passed through on a winning escape, once per game
+        if let GameState::WinDefender = replay[num_plies].0.who_won() {
+            let mut visited = [false; BOARDSIZE * BOARDSIZE];
+            for (mov, (board, _)) in record.get_moves().iter().zip(&replay) {
+                if board.is_king_move(mov) {
+                    for pos in squares_on_path(mov) {
+                        visited[pos.get_num()] = true;
+                    }
+                }
+            }
+            for (num, _) in visited.iter().enumerate().filter(|(_, &on_path)| on_path) {
+                self.king_escape_heatmap.add(&Position::new_n(num));
+            }
+        }
+
+        self.game_lengths.push(num_plies);
+    }
+
+    /// Returns the average number of legal moves at every ply
+    pub fn branching_factor_by_ply(&self) -> Vec<f64> {
+        self.legal_moves_by_ply
+            .iter()
+            .zip(&self.positions_by_ply)
+            .map(|(&moves, &positions)| {
+                if positions == 0 {
+                    0.0
+                } else {
+                    moves as f64 / positions as f64
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the number of games per length bucket as (first ply of bucket, count)
+    pub fn game_length_histogram(&self, bucket_size: usize) -> Vec<(usize, usize)> {
+        let max_len = self.game_lengths.iter().max().copied().unwrap_or(0);
+        let mut histogram: Vec<(usize, usize)> = (0..=max_len / bucket_size)
+            .map(|bucket| (bucket * bucket_size, 0))
+            .collect();
+
+        for len in &self.game_lengths {
+            histogram[len / bucket_size].1 += 1;
+        }
+        histogram
+    }
+
+    /// Returns the statistics per ply as CSV
+    pub fn per_ply_csv(&self) -> String {
+        let mut csv = String::from("ply,positions,captures,branching_factor\n");
+        for (ply, branching) in self.branching_factor_by_ply().iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{:.3}\n",
+                ply, self.positions_by_ply[ply], self.captures_by_ply[ply], branching
+            );
+        }
+        csv
+    }
+
+    /// Prints the analysis to the terminal.
+    /// Statistics per ply are grouped into buckets of `bucket_size` plies.
+    pub fn print(&self, bucket_size: usize) {
+        println!("Analysed {} games.", self.game_lengths.len());
+        println!("Captures per square:");
+        println!("{}", self.capture_heatmap);
+        println!("Squares the king crossed in games won by the defender, once per game:");
+        println!("{}", self.king_escape_heatmap);
+
+        println!("plies      captures  branching factor");
+        let branching = self.branching_factor_by_ply();
+        for start in (0..branching.len()).step_by(bucket_size) {
+            let end = (start + bucket_size).min(branching.len());
+            let captures: usize = self.captures_by_ply[start..end].iter().sum();
+            let moves: usize = self.legal_moves_by_ply[start..end].iter().sum();
+            let positions: usize = self.positions_by_ply[start..end].iter().sum();
+            let avg_branching = if positions == 0 {
+                0.0
+            } else {
+                moves as f64 / positions as f64
+            };
+            println!(
+                "{:>4}-{:<4} {:>9} {:>17.1}",
+                start,
+                end - 1,
+                captures,
+                avg_branching
+            );
+        }
+
+        println!("Game lengths:");
+        let histogram = self.game_length_histogram(bucket_size);
+        let max_count = histogram.iter().map(|(_, count)| *count).max().unwrap_or(1);
+        for (start, count) in histogram {
+            println!(
+                "{:>4}-{:<4} {:>6} {}",
+                start,
+                start + bucket_size - 1,
+                count,
+                "#".repeat(count * 50 / max_count.max(1))
+            );
+        }
+    }
+
+    /// Writes the heatmaps and per ply statistics as CSV files into the directory
+    pub fn write_csv(&self, dir: &Path, bucket_size: usize) -> Result<(), std::io::Error> {
+        fs::create_dir_all(dir)?;
+        fs::write(
+            dir.join("capture_heatmap.csv"),
+            self.capture_heatmap.to_csv(),
+        )?;
+        fs::write(
+            dir.join("king_escape_heatmap.csv"),
+            self.king_escape_heatmap.to_csv(),
+        )?;
+        fs::write(dir.join("per_ply.csv"), self.per_ply_csv())?;
+
+        let mut histogram = String::from("first_ply,games\n");
+        for (start, count) in self.game_length_histogram(bucket_size) {
+            histogram += &format!("{},{}\n", start, count);
+        }
+        fs::write(dir.join("game_lengths.csv"), histogram)
+    }
+}
+
+/// returns the squares a move slides over, including the start and the end
+fn squares_on_path(mov: &Move) -> Vec<Position> {
+    let (start, end) = (mov.get_start_pos(), mov.get_end_pos());
+    let (x_min, x_max) = (
+        start.get_x().min(end.get_x()),
+        start.get_x().max(end.get_x()),
+    );
+    let (y_min, y_max) = (
+        start.get_y().min(end.get_y()),
+        start.get_y().max(end.get_y()),
+    );
+    (x_min..=x_max)
+        .flat_map(|x| (y_min..=y_max).map(move |y| Position::new_xy(x, y)))
+        .collect()
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use crate::game::board::{Board, GameState};
+
+use super::record::GameRecord;
+
+/// Patterns of the king that can be searched for in the corpus
+pub enum KingPattern {
+    /// the king stands on the edge of the board
+    OnEdge,
+    /// the king can reach the edge of the board in one move
+    FreeFile,
+    /// the king stands on the edge and can reach a corner in one move
+    OnEdgeWithFreeFile,
+}
+
+/// A unique game of the corpus together with its outcome
+pub struct GameEntry {
+    record: GameRecord,
+    result: GameState,
+    occurrences: usize,
+}
+
+/// The first ply at which a game reached a position or pattern
+pub struct PositionHit {
+    game_idx: usize,
+    ply: usize,
+}
+
+/// The outcomes of a set of games
+pub struct OutcomeSummary {
+    pub attacker_wins: usize,
+    pub defender_wins: usize,
+    pub draws: usize,
+    pub unfinished: usize,
+}
+
+/// Index over a corpus of games answering which games reached a position
+pub struct CorpusIndex {
+    games: Vec<GameEntry>,
+    game_keys: HashMap<u64, Vec<usize>>,
+    positions: HashMap<u64, Vec<PositionHit>>,
+    num_duplicates: usize,
+}
+
+impl KingPattern {
+    /// returns true if the board matches the pattern
+    pub fn matches(&self, board: &Board) -> bool {
+        let king_pos = match board.get_king_pos() {
+            Some(pos) => pos,
+            None => return false,
+        };
+
+        match self {
+            KingPattern::OnEdge => king_pos.is_edge(),
+            KingPattern::FreeFile => {
+                !king_pos.is_edge()
+                    && board
+                        .possible_moves_from_pos(&king_pos)
+                        .unwrap()
+                        .iter()
+                        .any(|mov| mov.get_end_pos().is_edge())
+            }
+            KingPattern::OnEdgeWithFreeFile => {
+                king_pos.is_edge() && !board.king_escape_moves().is_empty()
+            }
+        }
+    }
+}
+
+impl GameEntry {
+    /// Get the record of the game
+    pub fn get_record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// Get the outcome of the game
+    pub fn get_result(&self) -> GameState {
+        self.result
+    }
+
+    /// Get how often the game occurs in the corpus
+    pub fn get_occurrences(&self) -> usize {
+        self.occurrences
+    }
+}
+
+impl PositionHit {
+    /// Get the index of the game in the corpus
+    pub fn get_game_idx(&self) -> usize {
+        self.game_idx
+    }
+
+    /// Get the ply at which the position was reached
+    pub fn get_ply(&self) -> usize {
+        self.ply
+    }
+}
+
+impl CorpusIndex {
+    /// Create an empty index
+    pub fn new() -> CorpusIndex {
+        CorpusIndex {
+            games: vec![],
+            game_keys: HashMap::new(),
+            positions: HashMap::new(),
+            num_duplicates: 0,
+        }
+    }
+
+    /// Builds an index over the given records
+    pub fn from_records(records: Vec<GameRecord>) -> CorpusIndex {
+        let mut index = CorpusIndex::new();
+        for record in records {
+            index.add_game(record);
+        }
+        index
+    }
+
+    /// Adds a game to the index.
+    /// Returns false if an identical game is already indexed.
+    pub fn add_game(&mut self, record: GameRecord) -> bool {
+        let replay = record.replay();
+        let hashes: Vec<u64> = replay.iter().map(|(board, _)| board.get_hash()).collect();
+
+        // the key of a game combines the hashes of all positions in order
+        let game_key = hashes.iter().fold(hashes.len() as u64, |key, hash| {
+            key.rotate_left(7).wrapping_mul(0x100_0000_01B3) ^ hash
+        });
+
+        let same_key = self.game_keys.entry(game_key).or_default();
+        if let Some(&game_idx) = same_key
+            .iter()
+            .find(|&&idx| self.games[idx].record.get_moves() == record.get_moves())
+        {
+            self.games[game_idx].occurrences += 1;
+            self.num_duplicates += 1;
+            return false;
+        }
+
+        let game_idx = self.games.len();
+        same_key.push(game_idx);
+
+        for (ply, hash) in hashes.into_iter().enumerate() {
+            let hits = self.positions.entry(hash).or_default();
+
+            // only the first time a game reaches a position is stored
+            if hits.last().is_some_and(|hit| hit.game_idx == game_idx) {
+                continue;
+            }
+            hits.push(PositionHit { game_idx, ply });
+        }
+
+        let result = record.get_result();
+        self.games.push(GameEntry {
+            record,
+            result,
+            occurrences: 1,
+        });
+        true
+    }
+
+    /// Get the game with the given index
+    pub fn get_game(&self, game_idx: usize) -> &GameEntry {
+        &self.games[game_idx]
+    }
+
+    /// Returns the number of unique games
+    pub fn num_games(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns the number of games that were dropped as duplicates
+    pub fn num_duplicates(&self) -> usize {
+        self.num_duplicates
+    }
+
+    /// Returns the number of distinct positions in the corpus
+    pub fn num_positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns every game that reached the given position
+    pub fn find_position(&self, board: &Board) -> Vec<&PositionHit> {
+        match self.positions.get(&board.get_hash()) {
+            // positions are compared to guard against hash collisions
+            Some(hits) => hits
+                .iter()
+                .filter(|hit| self.games[hit.game_idx].record.board_at(hit.ply) == *board)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns every game that reached a position matching the pattern
+    pub fn find_pattern(&self, pattern: &KingPattern) -> Vec<PositionHit> {
+        let mut hits = vec![];
+
+        for (game_idx, game) in self.games.iter().enumerate() {
+            if let Some(ply) = game
+                .record
+                .replay()
+                .iter()
+                .position(|(board, _)| pattern.matches(board))
+            {
+                hits.push(PositionHit { game_idx, ply });
+            }
+        }
+        hits
+    }
+
+    /// Returns how the games of the hits ended
+    pub fn summarize<'a>(&self, hits: impl Iterator<Item = &'a PositionHit>) -> OutcomeSummary {
+        let mut summary = OutcomeSummary {
+            attacker_wins: 0,
+            defender_wins: 0,
+            draws: 0,
+            unfinished: 0,
+        };
+
+        for hit in hits {
+            match self.games[hit.game_idx].result {
+                GameState::WinAttacker => summary.attacker_wins += 1,
+                GameState::WinDefender => summary.defender_wins += 1,
+                GameState::Draw => summary.draws += 1,
+                GameState::Undecided => summary.unfinished += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl std::fmt::Display for OutcomeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "attacker won {}, defender won {}, draws {}, unfinished {}",
+            self.attacker_wins, self.defender_wins, self.draws, self.unfinished
+        )
+    }
+}
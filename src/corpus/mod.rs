@@ -0,0 +1,3 @@
+pub mod analysis;
+pub mod index;
+pub mod record;
@@ -0,0 +1,314 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::game::{
+    board::{Board, GameState},
+    piece::PieceColor,
+    position::Position,
+    r#move::{Move, ParseMoveError},
+};
+
+#[derive(Debug)]
+pub struct ParseRecordError {
+    kind: RecordErrorKind,
+}
+
+#[derive(Debug)]
+enum RecordErrorKind {
+    Io(std::io::Error),
+    MoveError(usize, ParseMoveError),
+    IllegalMove(usize, Move),
+    MissingHeader(usize),
+    UnknownColor(usize, String),
+}
+
+/// A single game as stored in a replay file.
+/// A game that ended by a loss on time is followed by a line 'Flagged: attacker' or 'Flagged: defender'.
+#[derive(Clone)]
+pub struct GameRecord {
+    source: String,
+    game_id: usize,
+    moves: Vec<Move>,
+    /// the side that ran out of time
+    flagged: Option<PieceColor>,
+}
+
+impl GameRecord {
+    /// Create a new record from a list of moves
+    pub fn new(source: String, game_id: usize, moves: Vec<Move>) -> GameRecord {
+        GameRecord {
+            source,
+            game_id,
+            moves,
+            flagged: None,
+        }
+    }
+
+    /// Marks that the side lost the game on time
+    pub fn set_flagged(&mut self, color: PieceColor) {
+        self.flagged = Some(color);
+    }
+
+    /// Get the name of the file the game was loaded from
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the id of the game inside its file
+    pub fn get_game_id(&self) -> usize {
+        self.game_id
+    }
+
+    /// Get the moves of the game
+    pub fn get_moves(&self) -> &Vec<Move> {
+        &self.moves
+    }
+
+    /// Returns the board after every ply (starting with the initial board)
+    /// together with the positions captured by the move leading to it.
+    pub fn replay(&self) -> Vec<(Board, Vec<Position>)> {
+        let mut board = Board::new();
+        let mut positions = Vec::with_capacity(self.moves.len() + 1);
+        positions.push((board.clone(), vec![]));
+
+        for mov in &self.moves {
+            let captured = board.make_move_captured_positions(mov);
+            positions.push((board.clone(), captured));
+        }
+
+        positions
+    }
+
+    /// Returns the board reached after the given number of plies
+    pub fn board_at(&self, ply: usize) -> Board {
+        let mut board = Board::new();
+        for mov in self.moves.iter().take(ply) {
+            board.make_move_captured_positions(mov);
+        }
+        board
+    }
+
+    /// Returns the outcome of the game.
+    /// Games that were stopped by the move limit are `Undecided`.
+    pub fn get_result(&self) -> GameState {
+        match self.flagged {
+            Some(PieceColor::Attacker) => GameState::WinDefender,
+            Some(PieceColor::Defender) => GameState::WinAttacker,
+            None => self.board_at(self.moves.len()).who_won(),
+        }
+    }
+}
+
+/// Loads all games of a single replay file.
+/// Every move is checked for legality while loading.
+pub fn load_replay_file(path: &Path) -> Result<Vec<GameRecord>, ParseRecordError> {
+    let file = File::open(path).map_err(|err| ParseRecordError {
+        kind: RecordErrorKind::Io(err),
+    })?;
+    let source = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut records = Vec::new();
+    let mut current: Option<(GameRecord, Board)> = None;
+
+    for (line_idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| ParseRecordError {
+            kind: RecordErrorKind::Io(err),
+        })?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(id) = line.strip_prefix("Game:") {
+            if let Some((record, _)) = current.take() {
+                records.push(record);
+            }
+            let game_id = id.trim().parse::<usize>().unwrap_or(records.len());
+            current = Some((
+                GameRecord::new(source.clone(), game_id, vec![]),
+                Board::new(),
+            ));
+            continue;
+        }
+
+        let (record, board) = current.as_mut().ok_or(ParseRecordError {
+            kind: RecordErrorKind::MissingHeader(line_idx + 1),
+        })?;
+
+        if let Some(color) = line.strip_prefix("Flagged:") {
+            let color = match color.trim() {
+                "attacker" => PieceColor::Attacker,
+                "defender" => PieceColor::Defender,
+                other => {
+                    return Err(ParseRecordError {
+                        kind: RecordErrorKind::UnknownColor(line_idx + 1, other.to_string()),
+                    })
+                }
+            };
+            record.set_flagged(color);
+            continue;
+        }
+
+        let mov = Move::from_record_str(line).map_err(|err| ParseRecordError {
+            kind: RecordErrorKind::MoveError(line_idx + 1, err),
+        })?;
+
+        if board.is_game_over() || !board.is_valid_move(&mov, &board.get_player()) {
+            return Err(ParseRecordError {
+                kind: RecordErrorKind::IllegalMove(line_idx + 1, mov),
+            });
+        }
+
+        board.make_move_captured_positions(&mov);
+        record.moves.push(mov);
+    }
+
+    if let Some((record, _)) = current {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Loads all replay files of a directory.
+/// Files that can not be parsed are reported and skipped.
+pub fn load_replay_dir(path: &Path) -> Vec<GameRecord> {
+    let mut records = Vec::new();
+    for file in list_replay_files(path) {
+        match load_replay_file(&file) {
+            Ok(mut games) => records.append(&mut games),
+            Err(err) => println!("Skipping {}: {}", file.display(), err),
+        }
+    }
+    records
+}
+
+/// Returns the paths of all files in the given directory
+pub fn list_replay_files(path: &Path) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<std::path::PathBuf> = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(_) => vec![],
+    };
+    files.sort();
+    files
+}
+
+impl std::fmt::Display for ParseRecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            RecordErrorKind::Io(err) => write!(f, "file could not be read: {}", err),
+            RecordErrorKind::MoveError(line, err) => {
+                write!(f, "line {}: move could not be parsed: {}", line, err)
+            }
+            RecordErrorKind::IllegalMove(line, mov) => {
+                write!(f, "line {}: illegal move {}", line, mov)
+            }
+            RecordErrorKind::MissingHeader(line) => {
+                write!(f, "line {}: move found before a 'Game:' header", line)
+            }
+            RecordErrorKind::UnknownColor(line, color) => {
+                write!(
+                    f,
+                    "line {}: unknown color '{}' of the flagged side",
+                    line, color
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads the replay text through a temporary file
+    fn load_str(name: &str, content: &str) -> Result<Vec<GameRecord>, ParseRecordError> {
+        let dir = std::env::temp_dir().join(format!("record_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        let result = load_replay_file(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn flagged_line_decides_the_result() {
+        let records = load_str(
+            "flagged",
+            "Game: 0\n(7, A) -> (9, A)\n(7, F) -> (7, A)\nFlagged: defender\n\
+             Game: 1\n(7, A) -> (9, A)\nFlagged: attacker\n\
+             Game: 2\n(7, A) -> (9, A)\n",
+        )
+        .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].get_moves().len(), 2);
+        assert!(records[0].get_result() == GameState::WinAttacker);
+        assert!(records[1].get_result() == GameState::WinDefender);
+        assert!(records[2].get_result() == GameState::Undecided);
+    }
+
+    #[test]
+    fn unknown_flagged_color_is_an_error() {
+        let err = load_str("color", "Game: 0\n(7, A) -> (9, A)\nFlagged: nobody\n")
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err.kind, RecordErrorKind::UnknownColor(3, ref color) if color == "nobody")
+        );
+    }
+
+    #[test]
+    fn malformed_columns_are_errors() {
+        for line in [
+            "(7, !) -> (9, A)",
+            "(7, Ł) -> (9, A)",
+            "(7, AB) -> (9, A)",
+            "(7, a) -> (9, A)",
+            "(7, L) -> (9, A)",
+            "(11, A) -> (9, A)",
+            "(7, A) -> (9)",
+        ] {
+            let err = load_str("column", &format!("Game: 0\n{}\n", line))
+                .err()
+                .unwrap();
+            assert!(
+                matches!(err.kind, RecordErrorKind::MoveError(2, _)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_errors() {
+        for line in ["(7, A) -> (9, C)", "(9, A) -> (9, B)", "(5, F) -> (5, E)"] {
+            let err = load_str("illegal", &format!("Game: 0\n{}\n", line))
+                .err()
+                .unwrap();
+            assert!(
+                matches!(err.kind, RecordErrorKind::IllegalMove(2, _)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn move_before_header_is_an_error() {
+        let err = load_str("header", "(7, A) -> (9, A)\n").err().unwrap();
+        assert!(matches!(err.kind, RecordErrorKind::MissingHeader(1)));
+    }
+}
@@ -0,0 +1,111 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use crate::{
+    game::{
+        board::{Board, GameState},
+        piece::PieceColor,
+        r#move::Move,
+    },
+    utils::rng,
+};
+
+use super::{
+    move_heuristic::{blocking_fields, heuristic_weight},
+    Eval, EvalInit,
+};
+
+pub struct HeuristicRolloutParam<T: Eval> {
+    /// probability of playing a uniformly random move instead of a heuristic one
+    pub(crate) epsilon: f64,
+    /// plies after which the rollout is stopped, None to play until the game is over
+    pub(crate) max_depth: Option<usize>,
+    /// evaluation of the position at the cutoff, it should lie in [-1, 1] like the game results
+    pub(crate) cutoff_eval: T,
+}
+
+/// Rollout that prefers captures, king moves towards open corner lines and blocking moves.
+/// It can be cut off at a depth and scored by a static evaluation.
+pub struct HeuristicRollout<T: Eval> {
+    epsilon: f64,
+    max_depth: Option<usize>,
+    cutoff_eval: T,
+}
+
+impl<T: Eval> EvalInit for HeuristicRollout<T> {
+    type Param = HeuristicRolloutParam<T>;
+
+    fn new(param: Self::Param) -> Self {
+        HeuristicRollout {
+            epsilon: param.epsilon,
+            max_depth: param.max_depth,
+            cutoff_eval: param.cutoff_eval,
+        }
+    }
+}
+
+impl<T: Eval> HeuristicRollout<T> {
+    /// plays heuristic moves until the game is over or the depth is reached
+    /// and records them if a playout is given
+    fn rollout(&self, board: &Board, mut playout: Option<&mut Vec<Move>>) -> f64 {
+        let mut rollout_board = board.clone();
+        let mut depth = 0;
+
+        while !rollout_board.is_game_over() {
+            if self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                return self.cutoff_eval.get_eval(&rollout_board);
+            }
+
+            if let Some(mov) = self.choose_move(&rollout_board) {
+                rollout_board.make_move_captured_positions(&mov);
+                if let Some(playout) = playout.as_mut() {
+                    playout.push(mov);
+                }
+                depth += 1;
+            } else {
+                // if player is unable to move, the other party wins
+                return match rollout_board.get_player() {
+                    PieceColor::Attacker => -1.0,
+                    PieceColor::Defender => 1.0,
+                };
+            }
+        }
+
+        match rollout_board.who_won() {
+            GameState::WinAttacker => 1.0,
+            GameState::WinDefender => -1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// returns a random move with probability epsilon,
+    /// otherwise a move sampled in proportion to its heuristic weight
+    fn choose_move(&self, board: &Board) -> Option<Move> {
+        if rng::with_rng(|rng| rng.gen::<f64>()) < self.epsilon {
+            return board.get_random_move();
+        }
+
+        let moves = board.get_legal_moves();
+        let blocking_fields = blocking_fields(board);
+        let weights: Vec<f64> = moves
+            .iter()
+            .map(|mov| heuristic_weight(board, mov, &blocking_fields))
+            .collect();
+
+        let dist = WeightedIndex::new(&weights).ok()?;
+        Some(moves[rng::with_rng(|rng| dist.sample(rng))].clone())
+    }
+}
+
+impl<T: Eval> Eval for HeuristicRollout<T> {
+    fn get_eval(&self, board: &Board) -> f64 {
+        self.rollout(board, None)
+    }
+
+    fn get_eval_playout(&self, board: &Board, playout: &mut Vec<Move>) -> f64 {
+        self.rollout(board, Some(playout))
+    }
+
+    fn update(&mut self, board: Board) {
+        self.cutoff_eval.update(board);
+    }
+}
@@ -1,9 +1,12 @@
-use crate::game::board::Board;
+use crate::game::{board::Board, r#move::Move};
 
+pub mod heuristic_rollout;
 pub mod human_score;
+pub mod move_heuristic;
 pub mod neural_net;
 pub mod random_rollout;
 pub mod random_rollout_parallel;
+pub mod squashed;
 
 pub trait EvalInit {
     type Param;
@@ -15,6 +18,12 @@ pub trait Eval {
     /// Returns the evaluation of the given board.
     fn get_eval(&self, board: &Board) -> f64;
 
+    /// Returns the evaluation of the given board and records the moves played to reach it,
+    /// e.g. during a rollout. Evaluations that do not play any moves record nothing.
+    fn get_eval_playout(&self, board: &Board, _playout: &mut Vec<Move>) -> f64 {
+        self.get_eval(board)
+    }
+
     /// Updates the evaluation with the given board.
     #[allow(unused)]
     fn update(&mut self, board: Board);
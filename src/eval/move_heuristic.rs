@@ -0,0 +1,83 @@
+use crate::game::{
+    board::{Board, BOARDSIZE},
+    piece::PieceColor,
+    position::Position,
+    r#move::Move,
+};
+
+/// weight every move starts with
+const BASE_WEIGHT: f64 = 1.0;
+/// weight added for every captured piece
+const CAPTURE_WEIGHT: f64 = 3.0;
+/// weight of a king move to a corner
+const ESCAPE_WEIGHT: f64 = 100.0;
+/// weight of a king move to a field with an open line to a corner
+const THREAT_WEIGHT: f64 = 5.0;
+/// weight added for every step the king gets closer to a corner
+const APPROACH_WEIGHT: f64 = 1.0;
+/// weight of an attacker move onto an open line between the king and a corner
+const BLOCK_WEIGHT: f64 = 20.0;
+
+/// returns the fields on the open lines between the king and the corners
+/// if the attacker is to move, so moves onto them can be preferred
+pub fn blocking_fields(board: &Board) -> Vec<Position> {
+    match board.get_king_pos() {
+        Some(king_pos) if board.get_player() == PieceColor::Attacker => {
+            open_corner_lines(board, &king_pos, &king_pos)
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// returns the heuristic weight of a move, the fields on open lines of the king
+/// to the corners are given to find blocking moves
+pub fn heuristic_weight(board: &Board, mov: &Move, blocking_fields: &[Position]) -> f64 {
+    let mut weight = BASE_WEIGHT + CAPTURE_WEIGHT * board.num_captures(mov) as f64;
+
+    if board.is_king_move(mov) {
+        let start = mov.get_start_pos();
+        let end = mov.get_end_pos();
+        if end.is_corner() {
+            return ESCAPE_WEIGHT;
+        }
+        if !open_corner_lines(board, end, start).is_empty() {
+            weight += THREAT_WEIGHT;
+        }
+        let approach = start.min_dist_to_corner() as f64 - end.min_dist_to_corner() as f64;
+        weight += APPROACH_WEIGHT * approach.max(0.0);
+    } else if blocking_fields.contains(mov.get_end_pos()) {
+        weight += BLOCK_WEIGHT;
+    }
+
+    weight
+}
+
+/// returns the free fields between the position and every corner it can reach in a straight line.
+/// The vacated position is treated as empty, so lines of a piece after its move can be checked.
+pub fn open_corner_lines(board: &Board, pos: &Position, vacated: &Position) -> Vec<Vec<Position>> {
+    let (x, y) = (pos.get_x(), pos.get_y());
+    let last = BOARDSIZE - 1;
+    let mut lines: Vec<Vec<Position>> = vec![];
+
+    // corners in the same row, if the position is on the upper or lower edge
+    if x == 0 || x == last {
+        lines.push((1..y).map(|y| Position::new_xy(x, y)).collect());
+        lines.push((y + 1..last).map(|y| Position::new_xy(x, y)).collect());
+    }
+    // corners in the same column, if the position is on the left or right edge
+    if y == 0 || y == last {
+        lines.push((1..x).map(|x| Position::new_xy(x, y)).collect());
+        lines.push((x + 1..last).map(|x| Position::new_xy(x, y)).collect());
+    }
+
+    lines
+        .into_iter()
+        .filter(|line| {
+            line.iter()
+                .all(|field| field == vacated || !board.pos_is_occupied(field))
+        })
+        .collect()
+}
@@ -1,6 +1,7 @@
 use crate::game::{
     board::{Board, GameState},
     piece::PieceColor,
+    r#move::Move,
 };
 
 use super::{Eval, EvalInit};
@@ -15,8 +16,9 @@ impl EvalInit for RandomRollout {
     }
 }
 
-impl Eval for RandomRollout {
-    fn get_eval(&self, board: &Board) -> f64 {
+impl RandomRollout {
+    /// plays random moves until the game is over and records them if a playout is given
+    fn rollout(&self, board: &Board, mut playout: Option<&mut Vec<Move>>) -> f64 {
         let mut rollout_board = board.clone();
 
         // perform actions as long as the game is not over
@@ -25,6 +27,9 @@ impl Eval for RandomRollout {
             if let Some(mov) = rollout_board.get_random_move() {
                 // perform random move and imcrement counter
                 rollout_board.make_move_captured_positions(&mov);
+                if let Some(playout) = playout.as_mut() {
+                    playout.push(mov);
+                }
             } else {
                 // if player is unable to move, other pary wins
                 return match rollout_board.get_player() {
@@ -40,6 +45,16 @@ impl Eval for RandomRollout {
             _ => 0.0,
         }
     }
+}
+
+impl Eval for RandomRollout {
+    fn get_eval(&self, board: &Board) -> f64 {
+        self.rollout(board, None)
+    }
+
+    fn get_eval_playout(&self, board: &Board, playout: &mut Vec<Move>) -> f64 {
+        self.rollout(board, Some(playout))
+    }
 
     fn update(&mut self, _board: Board) {}
 }
@@ -0,0 +1,32 @@
+use crate::game::board::Board;
+
+use super::{Eval, EvalInit};
+
+/// Squashes an evaluation into [-1, 1] like the outcome of a rollout,
+/// so a static evaluation can be used as value function by MCTS
+pub struct Squashed<T: Eval> {
+    eval_fn: T,
+    scale: f64,
+}
+
+impl<T: Eval> EvalInit for Squashed<T> {
+    /// the evaluation and the value that is mapped to tanh(1)
+    type Param = (T, f64);
+
+    fn new(param: Self::Param) -> Self {
+        Squashed {
+            eval_fn: param.0,
+            scale: param.1,
+        }
+    }
+}
+
+impl<T: Eval> Eval for Squashed<T> {
+    fn get_eval(&self, board: &Board) -> f64 {
+        (self.eval_fn.get_eval(board) / self.scale).tanh()
+    }
+
+    fn update(&mut self, board: Board) {
+        self.eval_fn.update(board);
+    }
+}
@@ -2,15 +2,17 @@ use std::fmt::Display;
 
 use tch::Tensor;
 
-use crate::utils::action::Action;
+use crate::utils::{action::Action, rng};
 
 use super::{
     piece::{Piece, PieceColor},
     position::Position,
     r#move::Move,
+    zobrist::{self, ATTACKER_IDX, DEFENDER_IDX, KING_IDX},
 };
 use rand::prelude::SliceRandom;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GameState {
     Undecided,
     WinAttacker,
@@ -56,12 +58,11 @@ impl Board {
 
     /// Returns a random possible move for the provided color
     pub fn get_random_move_color(&self, color: &PieceColor) -> Option<Move> {
-        let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-
-        match color {
-            PieceColor::Attacker => self.attacker_moves.choose(&mut rng).cloned(),
-            PieceColor::Defender => self.defender_moves.choose(&mut rng).cloned(),
-        }
+        let moves = match color {
+            PieceColor::Attacker => &self.attacker_moves,
+            PieceColor::Defender => &self.defender_moves,
+        };
+        rng::with_rng(|rng| moves.choose(rng).cloned())
     }
 
     pub fn get_legal_moves(&self) -> Vec<Move> {
@@ -322,6 +323,57 @@ impl Board {
         captured_positions
     }
 
+    /// Passes the turn to the other player without moving a piece
+    pub fn make_null_move(&mut self) {
+        self.player.flip();
+    }
+
+    /// Returns the number of pieces the move would capture without performing it
+    pub fn num_captures(&self, mov: &Move) -> usize {
+        let start_pos = mov.get_start_pos();
+        let end_pos = mov.get_end_pos();
+        let color = match self.get_piece(start_pos) {
+            Some(piece) => piece.get_color(),
+            None => return 0,
+        };
+        let enemy_color = color.get_opposite();
+
+        // positions holding one of our pieces after the move
+        let is_own = |pos: &Position| {
+            pos == end_pos || (pos != start_pos && self.has_color_piece(pos, &color))
+        };
+
+        let mut captures = 0;
+        for (sur_pos, one_after) in end_pos.get_sur_pos_and_one_after() {
+            if !self.has_color_piece(&sur_pos, &enemy_color) {
+                continue;
+            }
+
+            if self
+                .get_king_pos()
+                .is_some_and(|king_pos| king_pos == sur_pos)
+            {
+                if sur_pos
+                    .get_surrounding_pos()
+                    .iter()
+                    .all(|king_sur| is_own(king_sur) || king_sur.is_throne())
+                {
+                    captures += 1;
+                    break;
+                }
+            } else if is_own(&one_after) || one_after.is_throne() {
+                captures += 1;
+            }
+        }
+        captures
+    }
+
+    /// Returns true if the move is made by the king
+    pub fn is_king_move(&self, mov: &Move) -> bool {
+        self.get_king_pos()
+            .is_some_and(|king_pos| king_pos == *mov.get_start_pos())
+    }
+
     /// computes the current state of the game
     pub fn who_won(&self) -> GameState {
         let attacker_moves_cnt = self.attacker_moves.len();
@@ -420,6 +472,41 @@ impl Board {
     pub fn get_king(&self) -> &u128 {
         &self.defenders
     }
+
+    /// Returns the moves of the king that end on a corner
+    pub fn king_escape_moves(&self) -> Vec<Move> {
+        match self.get_king_pos() {
+            Some(king_pos) => self
+                .possible_moves_from_pos(&king_pos)
+                .unwrap()
+                .into_iter()
+                .filter(|mov| mov.get_end_pos().is_corner())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the zobrist hash of the position including the player to move
+    pub fn get_hash(&self) -> u64 {
+        let mut hash = zobrist::hash_bitboard(self.attackers, ATTACKER_IDX)
+            ^ zobrist::hash_bitboard(self.defenders, DEFENDER_IDX)
+            ^ zobrist::hash_bitboard(self.king, KING_IDX);
+
+        if self.player == PieceColor::Defender {
+            hash ^= zobrist::DEFENDER_TO_MOVE_KEY;
+        }
+        hash
+    }
+}
+
+impl PartialEq for Board {
+    /// boards are equal if the pieces and the player to move are equal
+    fn eq(&self, other: &Self) -> bool {
+        self.attackers == other.attackers
+            && self.defenders == other.defenders
+            && self.king == other.king
+            && self.player == other.player
+    }
 }
 
 impl Display for Board {
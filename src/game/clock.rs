@@ -0,0 +1,122 @@
+use std::time;
+
+use super::piece::PieceColor;
+
+/// Time of one side in ms: what is left on its clock and what it gets after every move
+#[derive(Clone, Copy)]
+pub struct SideTime {
+    pub(crate) remaining_ms: u128,
+    pub(crate) increment_ms: u128,
+}
+
+/// Chess clock of a game. Sides without a time are not timed.
+pub struct Clock {
+    attacker: Option<SideTime>,
+    defender: Option<SideTime>,
+    /// when the side to move started thinking
+    move_start: time::Instant,
+    flagged: Option<PieceColor>,
+}
+
+impl SideTime {
+    /// Create the time of a side from the main time and the increment in ms
+    pub fn new(main_ms: u128, increment_ms: u128) -> SideTime {
+        SideTime {
+            remaining_ms: main_ms,
+            increment_ms,
+        }
+    }
+}
+
+impl Clock {
+    /// Create a clock with the time controls of both sides
+    pub fn new(attacker: Option<SideTime>, defender: Option<SideTime>) -> Clock {
+        Clock {
+            attacker,
+            defender,
+            move_start: time::Instant::now(),
+            flagged: None,
+        }
+    }
+
+    /// returns the time of the side, None if it is not timed
+    pub fn get_time(&self, color: &PieceColor) -> Option<SideTime> {
+        match color {
+            PieceColor::Attacker => self.attacker,
+            PieceColor::Defender => self.defender,
+        }
+    }
+
+    /// Starts the clock of the side to move
+    pub fn start(&mut self) {
+        self.move_start = time::Instant::now();
+    }
+
+    /// Stops the clock of the side that has just moved and charges it the time it used.
+    /// Returns false if the side ran out of time, otherwise it gets its increment.
+    pub fn stop(&mut self, color: &PieceColor) -> bool {
+        let used = self.move_start.elapsed().as_millis();
+        let time = match color {
+            PieceColor::Attacker => self.attacker.as_mut(),
+            PieceColor::Defender => self.defender.as_mut(),
+        };
+        let Some(time) = time else {
+            return true;
+        };
+
+        if used > time.remaining_ms {
+            time.remaining_ms = 0;
+            self.flagged = Some(color.clone());
+            return false;
+        }
+        time.remaining_ms = time.remaining_ms - used + time.increment_ms;
+        true
+    }
+
+    /// returns the side that ran out of time
+    pub fn get_flagged(&self) -> Option<PieceColor> {
+        self.flagged.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn side_that_overruns_is_flagged_without_increment() {
+        let mut clock = Clock::new(Some(SideTime::new(5, 1000)), None);
+        clock.start();
+        thread::sleep(time::Duration::from_millis(20));
+
+        assert!(!clock.stop(&PieceColor::Attacker));
+        assert_eq!(
+            clock.get_time(&PieceColor::Attacker).unwrap().remaining_ms,
+            0
+        );
+        assert!(clock.get_flagged() == Some(PieceColor::Attacker));
+    }
+
+    #[test]
+    fn side_within_its_time_gets_the_increment() {
+        let mut clock = Clock::new(None, Some(SideTime::new(10_000, 500)));
+        clock.start();
+
+        assert!(clock.stop(&PieceColor::Defender));
+        let remaining = clock.get_time(&PieceColor::Defender).unwrap().remaining_ms;
+        assert!(remaining > 10_000 && remaining <= 10_500);
+        assert!(clock.get_flagged().is_none());
+    }
+
+    #[test]
+    fn untimed_side_is_never_flagged() {
+        let mut clock = Clock::new(None, None);
+        clock.start();
+        thread::sleep(time::Duration::from_millis(5));
+
+        assert!(clock.stop(&PieceColor::Attacker));
+        assert!(clock.get_flagged().is_none());
+    }
+}
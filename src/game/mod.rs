@@ -1,5 +1,7 @@
 pub mod board;
+pub mod clock;
 pub mod r#move;
 pub mod move_generation;
 pub mod piece;
 pub mod position;
+pub mod zobrist;
@@ -49,6 +49,15 @@ impl Move {
         })
     }
 
+    /// Create a move from a line of a replay file, e.g. "(7, A) -> (9, A)"
+    pub fn from_record_str(line: &str) -> Result<Move, ParseMoveError> {
+        let cleaned: String = line
+            .chars()
+            .map(|c| if "(),->".contains(c) { ' ' } else { c })
+            .collect();
+        Move::from_str(cleaned.split_whitespace())
+    }
+
     /// Create a new move
     pub fn new(start_pos: Position, end_pos: Position) -> Move {
         Move { start_pos, end_pos }
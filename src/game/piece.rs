@@ -40,14 +40,6 @@ impl Piece {
         }
     }
 
-    /// returns true if the piece is of the given color
-    pub fn is_color(&self, color: &PieceColor) -> bool {
-        match color {
-            PieceColor::Attacker => self.is_attacker(),
-            PieceColor::Defender => self.is_defender(),
-        }
-    }
-
     /// returns true if the piece is of the same color
     #[allow(unused)]
     pub fn same_color(&self, other: &Piece) -> bool {
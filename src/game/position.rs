@@ -36,11 +36,15 @@ impl Position {
             }),
         };
 
-        let letter_parse = |el: &str| match el.chars().next() {
-            Some(char) => Ok((char as u8 - b'A') as usize),
-            None => Err(ParsePositionError {
-                kind: PositionErrorKind::NonLetter,
-            }),
+        // only a single capital letter is converted, anything else could underflow or be truncated
+        let letter_parse = |el: &str| {
+            let mut chars = el.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) if char.is_ascii_uppercase() => Ok((char as u8 - b'A') as usize),
+                _ => Err(ParsePositionError {
+                    kind: PositionErrorKind::NonLetter,
+                }),
+            }
         };
 
         let i: usize = int_parse(elements[0])?;
@@ -115,6 +119,12 @@ impl Position {
         [0, 10, 110, 120].contains(&self.get_num())
     }
 
+    /// Returns true if the position is on the edge of the board
+    pub fn is_edge(&self) -> bool {
+        let (x, y) = (self.get_x(), self.get_y());
+        x == 0 || y == 0 || x == BOARDSIZE - 1 || y == BOARDSIZE - 1
+    }
+
     /// Returns a vector of (sur_pos, one_after)
     pub fn get_sur_pos_and_one_after(&self) -> Vec<(Position, Position)> {
         let mut sur_pos_and_one_after = Vec::<(Position, Position)>::new();
@@ -0,0 +1,53 @@
+use super::board::BOARDSIZE;
+
+const NUM_SQUARES: usize = BOARDSIZE * BOARDSIZE;
+
+/// Zobrist keys for attackers, defenders and the king on every square.
+/// The keys are generated at compile time so hashes are stable between runs
+/// and can be stored on disk.
+pub const PIECE_KEYS: [[u64; NUM_SQUARES]; 3] = generate_piece_keys();
+
+/// Zobrist key xored in when the defender is to move
+pub const DEFENDER_TO_MOVE_KEY: u64 = splitmix64(0x0DEF_E2DE_2000_0001).1;
+
+pub const ATTACKER_IDX: usize = 0;
+pub const DEFENDER_IDX: usize = 1;
+pub const KING_IDX: usize = 2;
+
+/// returns (next state, random number) of the splitmix64 generator
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_piece_keys() -> [[u64; NUM_SQUARES]; 3] {
+    let mut keys = [[0; NUM_SQUARES]; 3];
+    let mut state = 0x4E45_4641_5441_464C;
+    let mut piece = 0;
+    while piece < 3 {
+        let mut sq = 0;
+        while sq < NUM_SQUARES {
+            let (next_state, key) = splitmix64(state);
+            state = next_state;
+            keys[piece][sq] = key;
+            sq += 1;
+        }
+        piece += 1;
+    }
+    keys
+}
+
+/// xors the keys of all squares set in the bitboard
+pub fn hash_bitboard(bitboard: u128, piece_idx: usize) -> u64 {
+    let mut hash = 0;
+    let mut current = bitboard;
+    while current != 0 {
+        let sq = current.trailing_zeros() as usize;
+        hash ^= PIECE_KEYS[piece_idx][sq];
+        current &= current - 1;
+    }
+    hash
+}
@@ -0,0 +1,184 @@
+use std::{fmt::Display, time};
+
+use crate::{
+    agent::{
+        alpha_beta::policy::{AlphaBetaBot, AlphaBetaParams},
+        limits::SearchLimits,
+        mcts::policy::{Mcts, MctsParams},
+        Bot, BotInit,
+    },
+    eval::Eval,
+    game::board::Board,
+    utils::rng,
+};
+
+/// number of random plies played from the start to get the benchmark positions
+const BENCHMARK_PLIES: [usize; 3] = [0, 10, 20];
+/// seed of the random plies and the searches, so every run measures the same positions
+const BENCHMARK_SEED: u64 = 2024;
+/// depth limit of the alpha-beta benchmark, high enough that only the time ends the search
+const BENCHMARK_MAX_DEPTH: usize = 64;
+
+/// Playout rate and memory usage of MCTS summed over the benchmark positions
+pub struct MctsBenchmark {
+    pub positions: usize,
+    pub playouts: usize,
+    pub time_ms: u128,
+    pub tree_nodes: usize,
+    pub memory: usize,
+}
+
+impl MctsBenchmark {
+    /// Get the number of playouts per second
+    pub fn get_playouts_per_sec(&self) -> usize {
+        (self.playouts as u128 * 1000 / self.time_ms.max(1)) as usize
+    }
+
+    /// Get the average number of bytes used per expanded node
+    pub fn get_bytes_per_node(&self) -> usize {
+        self.memory / self.tree_nodes.max(1)
+    }
+}
+
+impl Display for MctsBenchmark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "positions: {}, playouts: {}, playouts/s: {}, tree nodes: {}, memory per node: {} bytes",
+            self.positions,
+            self.playouts,
+            self.get_playouts_per_sec(),
+            self.tree_nodes,
+            self.get_bytes_per_node()
+        )
+    }
+}
+
+/// Searches every benchmark position for the given time with a fresh tree
+/// and measures how many playouts are done in the elapsed time and how much memory the tree needs.
+/// Every iteration counts as a playout, also those ending in terminal or solved nodes.
+pub fn benchmark_mcts<T: Eval + Send + Sync>(
+    params: MctsParams,
+    eval_fn: T,
+    time_limit: u128,
+) -> MctsBenchmark {
+    let mut mcts = Mcts::new(params, eval_fn);
+    let mut benchmark = MctsBenchmark {
+        positions: 0,
+        playouts: 0,
+        time_ms: 0,
+        tree_nodes: 0,
+        memory: 0,
+    };
+
+    for board in benchmark_positions() {
+        mcts.reset(&board);
+        let start_time = time::Instant::now();
+        mcts.grow(&SearchLimits::movetime(time_limit).with_seed(Some(BENCHMARK_SEED)));
+        let elapsed = start_time.elapsed().as_millis();
+
+        benchmark.positions += 1;
+        benchmark.playouts += mcts.root_visits();
+        benchmark.time_ms += elapsed;
+        benchmark.tree_nodes += mcts.num_tree_nodes();
+        benchmark.memory += mcts.tree_memory_usage();
+    }
+
+    benchmark
+}
+
+/// Node rate and reached depth of alpha-beta with a number of threads summed over the benchmark positions
+pub struct AlphaBetaBenchmark {
+    pub threads: usize,
+    pub positions: usize,
+    pub nodes: usize,
+    pub time_ms: u128,
+    /// sum of the depths of the last completed iterations
+    pub depth: usize,
+}
+
+impl AlphaBetaBenchmark {
+    /// Get the number of nodes searched per second by all threads together
+    pub fn get_nodes_per_sec(&self) -> usize {
+        (self.nodes as u128 * 1000 / self.time_ms.max(1)) as usize
+    }
+
+    /// Get the average depth of the last completed iteration
+    pub fn get_avg_depth(&self) -> f64 {
+        self.depth as f64 / self.positions.max(1) as f64
+    }
+}
+
+impl Display for AlphaBetaBenchmark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "threads: {}, positions: {}, nodes: {}, nodes/s: {}, average depth: {:.2}",
+            self.threads,
+            self.positions,
+            self.nodes,
+            self.get_nodes_per_sec(),
+            self.get_avg_depth()
+        )
+    }
+}
+
+/// Searches every benchmark position for the given time with 1 up to max_threads threads
+/// and measures the node rate and the depth the main thread completed for each number of threads.
+/// Every search starts with an empty transposition table and all threads search the same positions.
+pub fn benchmark_alpha_beta<T: Eval + Send + Sync>(
+    max_threads: usize,
+    make_eval: impl Fn() -> T,
+    time_limit: u128,
+) -> Vec<AlphaBetaBenchmark> {
+    let boards = benchmark_positions();
+    let mut benchmarks = vec![];
+
+    for threads in 1..=max_threads {
+        let mut benchmark = AlphaBetaBenchmark {
+            threads,
+            positions: 0,
+            nodes: 0,
+            time_ms: 0,
+            depth: 0,
+        };
+
+        for board in &boards {
+            let mut params = AlphaBetaParams::new(BENCHMARK_MAX_DEPTH);
+            params.num_threads = threads;
+            let mut bot = AlphaBetaBot::new(params, make_eval());
+
+            let start_time = time::Instant::now();
+            let limits = SearchLimits::movetime(time_limit).with_seed(Some(BENCHMARK_SEED));
+            bot.get_next_move(board, &limits);
+            let elapsed = start_time.elapsed().as_millis();
+
+            benchmark.positions += 1;
+            benchmark.nodes += bot.num_nodes();
+            benchmark.time_ms += elapsed;
+            benchmark.depth += bot.get_search_info().map_or(0, |info| info.depth);
+        }
+        benchmarks.push(benchmark);
+    }
+
+    benchmarks
+}
+
+/// Plays the seeded random plies of the benchmark from the start to get its positions
+fn benchmark_positions() -> Vec<Board> {
+    rng::seed(BENCHMARK_SEED);
+    let mut boards = vec![];
+    for plies in BENCHMARK_PLIES {
+        let mut board = Board::new();
+        for _ in 0..plies {
+            match board.get_random_move() {
+                Some(mov) if !board.is_game_over() => {
+                    board.make_move_captured_positions(&mov);
+                }
+                _ => break,
+            }
+        }
+        boards.push(board);
+    }
+    boards
+}
@@ -9,9 +9,11 @@ use chrono::Local;
 const MAX_NUMBER_OF_MOVES: usize = 1000;
 
 use crate::{
-    agent::Bot,
+    agent::{limits::SearchLimits, Bot},
     game::{
         board::{Board, GameState},
+        clock::Clock,
+        piece::PieceColor,
         r#move::Move,
     },
 };
@@ -20,6 +22,8 @@ pub struct Arena<'a> {
     black_bot: &'a mut Box<dyn Bot>,
     white_bot: &'a mut Box<dyn Bot>,
     replay_buffer: Vec<(Move, Board)>,
+    /// the side that ran out of time in the last game
+    flagged: Option<PieceColor>,
 }
 
 pub struct FightInfo {
@@ -35,13 +39,15 @@ impl Arena<'_> {
             black_bot,
             white_bot,
             replay_buffer: Vec::new(),
+            flagged: None,
         }
     }
-    /// let the two bots of the arena fight with set time limits in ms
+    /// let the two bots of the arena fight with the given search limits.
+    /// A side with a clock in its limits loses if it runs out of time.
     pub fn fight_or_be_forgotten(
         &mut self,
-        time_to_think_black: u128,
-        time_to_think_white: u128,
+        black_limits: &SearchLimits,
+        white_limits: &SearchLimits,
         verbose: bool,
     ) -> FightInfo {
         let mut board = Board::new();
@@ -51,24 +57,54 @@ impl Arena<'_> {
 
         let mut num_black_nodes: Vec<usize> = Vec::with_capacity(MAX_NUMBER_OF_MOVES / 2);
         let mut num_white_nodes: Vec<usize> = Vec::with_capacity(MAX_NUMBER_OF_MOVES / 2);
+        let mut clock = Clock::new(black_limits.clock, white_limits.clock);
 
         while !board.is_game_over() && num_of_turns < MAX_NUMBER_OF_MOVES {
             if verbose {
                 println!("{}", board);
             }
+            let color = match black_move {
+                true => PieceColor::Attacker,
+                false => PieceColor::Defender,
+            };
+            clock.start();
             let mov = match black_move {
                 true => {
-                    let next_mov = self.black_bot.get_next_move(&board, time_to_think_black);
+                    let limits = black_limits.with_clock(clock.get_time(&color));
+                    let next_mov = self.black_bot.get_next_move(&board, &limits);
                     num_black_nodes.push(self.black_bot.num_nodes());
+                    if let Some(stats) = self.black_bot.get_search_stats().filter(|_| verbose) {
+                        println!("{}", stats);
+                    }
+                    if let Some(info) = self.black_bot.get_search_info().filter(|_| verbose) {
+                        println!("{}", info);
+                    }
                     next_mov
                 }
                 false => {
-                    let next_mov = self.white_bot.get_next_move(&board, time_to_think_white);
+                    let limits = white_limits.with_clock(clock.get_time(&color));
+                    let next_mov = self.white_bot.get_next_move(&board, &limits);
                     num_white_nodes.push(self.white_bot.num_nodes());
+                    if let Some(stats) = self.white_bot.get_search_stats().filter(|_| verbose) {
+                        println!("{}", stats);
+                    }
+                    if let Some(info) = self.white_bot.get_search_info().filter(|_| verbose) {
+                        println!("{}", info);
+                    }
                     next_mov
                 }
             };
 
+            if !clock.stop(&color) {
+                if verbose {
+                    match color {
+                        PieceColor::Attacker => println!("Black ran out of time"),
+                        PieceColor::Defender => println!("White ran out of time"),
+                    }
+                }
+                break;
+            }
+
             match mov {
                 Some(mov) => {
                     if verbose {
@@ -87,8 +123,15 @@ impl Arena<'_> {
             num_of_turns += 1;
         }
 
+        self.flagged = clock.get_flagged();
+        let state = match self.flagged {
+            Some(PieceColor::Attacker) => GameState::WinDefender,
+            Some(PieceColor::Defender) => GameState::WinAttacker,
+            None => board.who_won(),
+        };
+
         FightInfo {
-            state: board.who_won(),
+            state,
             num_turns: num_of_turns,
             black_nodes: num_black_nodes,
             white_nodes: num_white_nodes,
@@ -99,8 +142,8 @@ impl Arena<'_> {
     pub fn play_games(
         &mut self,
         num_games: usize,
-        time_to_think_black: u128,
-        time_to_think_white: u128,
+        black_limits: &SearchLimits,
+        white_limits: &SearchLimits,
         verbose: bool,
     ) {
         let date = Local::now().format("%Y-%m-%d-%H-%M-%S").to_string();
@@ -108,9 +151,9 @@ impl Arena<'_> {
             "{}-{}-{}-{}-{}-{}",
             date,
             self.black_bot.get_name(),
-            time_to_think_black,
+            black_limits,
             self.white_bot.get_name(),
-            time_to_think_white,
+            white_limits,
             num_games,
         );
         let ouput_results = File::create(format!("./results/{}", &file_name)).unwrap();
@@ -128,7 +171,7 @@ impl Arena<'_> {
 
         for game_idx in 0..num_games {
             let current_fight_info =
-                self.fight_or_be_forgotten(time_to_think_black, time_to_think_white, verbose);
+                self.fight_or_be_forgotten(black_limits, white_limits, verbose);
             num_moves.push(current_fight_info.num_turns);
 
             if let GameState::WinAttacker = current_fight_info.state {
@@ -155,7 +198,7 @@ impl Arena<'_> {
         println!("Finished");
     }
 
-    /// title encoding: <time> <black_bot_type> <black_time_to_think> <white_bot_type> <white_time_to_think> <number_of_games_played>
+    /// title encoding: <time> <black_bot_type> <black_limits> <white_bot_type> <white_limits> <number_of_games_played>
     /// last line in result file: <avg num of turns> <standard deviation of number of turns> <black winrate>
     fn write_fight_info(
         &self,
@@ -194,6 +237,11 @@ impl Arena<'_> {
             output.write_all(format!("{}\n", mov).as_bytes())?;
             // output.write_all(format!("{}", brd).as_bytes())?;
         }
+        match self.flagged {
+            Some(PieceColor::Attacker) => output.write_all("Flagged: attacker\n".as_bytes())?,
+            Some(PieceColor::Defender) => output.write_all("Flagged: defender\n".as_bytes())?,
+            None => {}
+        }
         let _ = output.flush();
         Ok(())
     }
@@ -1 +1,4 @@
+pub mod benchmark;
 pub mod fight;
+pub mod report;
+pub mod viewer;
@@ -0,0 +1,409 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::game::board::GameState;
+
+/// z value of the 95% confidence intervals
+const Z_95: f64 = 1.96;
+
+#[derive(Debug)]
+pub struct ParseResultsError {
+    kind: ResultsErrorKind,
+}
+
+#[derive(Debug)]
+enum ResultsErrorKind {
+    Io(std::io::Error),
+    FileName(String),
+    Line(usize),
+    NoGames,
+}
+
+/// The bot configuration encoded in the name of a results file
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MatchConfig {
+    black_bot: String,
+    black_limits: String,
+    white_bot: String,
+    white_limits: String,
+}
+
+/// Aggregated results of all games played with one configuration
+pub struct ConfigSummary {
+    games: usize,
+    black_wins: usize,
+    white_wins: usize,
+    draws: usize,
+    unfinished: usize,
+    turns: Vec<f64>,
+    black_nodes: Vec<f64>,
+    white_nodes: Vec<f64>,
+}
+
+/// Report over all result files of a directory grouped by bot configuration
+pub struct ResultsReport {
+    summaries: BTreeMap<MatchConfig, ConfigSummary>,
+}
+
+impl MatchConfig {
+    /// Parses a file name of the form
+    /// <date>-<black_bot>-<black_limits>-<white_bot>-<white_limits>-<num_games>
+    /// where the date consists of six parts.
+    pub fn from_file_name(name: &str) -> Option<MatchConfig> {
+        let parts: Vec<&str> = name.split('-').collect();
+        if parts.len() != 11 {
+            return None;
+        }
+
+        Some(MatchConfig {
+            black_bot: parts[6].to_string(),
+            black_limits: parts[7].to_string(),
+            white_bot: parts[8].to_string(),
+            white_limits: parts[9].to_string(),
+        })
+    }
+}
+
+impl ConfigSummary {
+    fn new() -> ConfigSummary {
+        ConfigSummary {
+            games: 0,
+            black_wins: 0,
+            white_wins: 0,
+            draws: 0,
+            unfinished: 0,
+            turns: vec![],
+            black_nodes: vec![],
+            white_nodes: vec![],
+        }
+    }
+
+    /// Adds a single game to the summary.
+    /// Node averages below zero mark missing data and are ignored.
+    fn add_game(&mut self, state: GameState, turns: f64, black_nodes: f64, white_nodes: f64) {
+        self.games += 1;
+        match state {
+            GameState::WinAttacker => self.black_wins += 1,
+            GameState::WinDefender => self.white_wins += 1,
+            GameState::Draw => self.draws += 1,
+            GameState::Undecided => self.unfinished += 1,
+        }
+        self.turns.push(turns);
+        if black_nodes >= 0.0 {
+            self.black_nodes.push(black_nodes);
+        }
+        if white_nodes >= 0.0 {
+            self.white_nodes.push(white_nodes);
+        }
+    }
+
+    /// Returns the score of black where draws and unfinished games count 0.5
+    fn black_score(&self) -> f64 {
+        (self.black_wins as f64 + 0.5 * (self.draws + self.unfinished) as f64) / self.games as f64
+    }
+
+    /// Returns the rating difference of black over white implied by the score.
+    /// A score of 0 or 1 is clamped to keep the value finite.
+    fn elo_diff(&self) -> f64 {
+        let score = self.black_score().clamp(0.001, 0.999);
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+
+    /// Returns the formatted columns of the summary
+    fn columns(&self) -> Vec<String> {
+        let n = self.games;
+        let rate = |k: usize| k as f64 / n as f64;
+        let interval = |k: usize| {
+            let (low, high) = wilson_interval(k, n, Z_95);
+            format!("{:.2} [{:.2}, {:.2}]", rate(k), low, high)
+        };
+
+        vec![
+            n.to_string(),
+            interval(self.black_wins),
+            interval(self.white_wins),
+            format!("{:.2}", rate(self.draws)),
+            format!("{:.2}", rate(self.unfinished)),
+            format!("{:.0}", self.elo_diff()),
+            format!("{:.1}", mean(&self.turns)),
+            format!("{:.1}", mean(&self.black_nodes)),
+            format!("{:.1}", mean(&self.white_nodes)),
+        ]
+    }
+}
+
+const COLUMN_NAMES: [&str; 13] = [
+    "black_bot",
+    "black_limits",
+    "white_bot",
+    "white_limits",
+    "games",
+    "black_win [95% CI]",
+    "white_win [95% CI]",
+    "draw",
+    "unfinished",
+    "elo_diff_black",
+    "avg_turns",
+    "avg_black_nodes",
+    "avg_white_nodes",
+];
+
+impl ResultsReport {
+    /// Create an empty report
+    pub fn new() -> ResultsReport {
+        ResultsReport {
+            summaries: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a report from all files of the given directory.
+    /// Files that can not be parsed are reported and skipped.
+    pub fn from_dir(path: &Path) -> ResultsReport {
+        let mut report = ResultsReport::new();
+
+        let mut files: Vec<std::path::PathBuf> = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect(),
+            Err(_) => vec![],
+        };
+        files.sort();
+
+        for file in files {
+            if let Err(err) = report.add_file(&file) {
+                println!("Skipping {}: {}", file.display(), err);
+            }
+        }
+        report
+    }
+
+    /// Adds all games of a results file to the report.
+    /// A file without games is rejected, so no configuration is reported without games.
+    pub fn add_file(&mut self, path: &Path) -> Result<(), ParseResultsError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let config = MatchConfig::from_file_name(&name).ok_or(ParseResultsError {
+            kind: ResultsErrorKind::FileName(name),
+        })?;
+
+        let file = File::open(path).map_err(|err| ParseResultsError {
+            kind: ResultsErrorKind::Io(err),
+        })?;
+
+        let mut games = vec![];
+        for (line_idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| ParseResultsError {
+                kind: ResultsErrorKind::Io(err),
+            })?;
+            let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+
+            // skip the header and the summary line at the end of the file
+            if line_idx == 0 || parts.len() != 7 {
+                continue;
+            }
+
+            let values: Vec<f64> = parts
+                .iter()
+                .map(|part| part.parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| ParseResultsError {
+                    kind: ResultsErrorKind::Line(line_idx + 1),
+                })?;
+
+            let state = match values[1] as i32 {
+                1 => GameState::WinAttacker,
+                -1 => GameState::WinDefender,
+                0 => GameState::Draw,
+                _ => GameState::Undecided,
+            };
+            games.push((state, values[2], values[3], values[5]));
+        }
+        if games.is_empty() {
+            return Err(ParseResultsError {
+                kind: ResultsErrorKind::NoGames,
+            });
+        }
+
+        let summary = self
+            .summaries
+            .entry(config)
+            .or_insert_with(ConfigSummary::new);
+        for (state, turns, black_nodes, white_nodes) in games {
+            summary.add_game(state, turns, black_nodes, white_nodes);
+        }
+        Ok(())
+    }
+
+    /// Returns the report as a Markdown table
+    pub fn to_markdown(&self) -> String {
+        let mut table = format!("| {} |\n", COLUMN_NAMES.join(" | "));
+        table += &format!("|{}\n", "---|".repeat(COLUMN_NAMES.len()));
+
+        for row in self.rows() {
+            table += &format!("| {} |\n", row.join(" | "));
+        }
+        table
+    }
+
+    /// Returns the report as CSV with separate columns for the confidence bounds
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "black_bot,black_limits,white_bot,white_limits,games,\
+            black_win,black_win_low,black_win_high,white_win,white_win_low,white_win_high,\
+            draw,unfinished,elo_diff_black,avg_turns,avg_black_nodes,avg_white_nodes\n",
+        );
+
+        for (config, summary) in &self.summaries {
+            let n = summary.games;
+            let (black_low, black_high) = wilson_interval(summary.black_wins, n, Z_95);
+            let (white_low, white_high) = wilson_interval(summary.white_wins, n, Z_95);
+
+            csv += &format!(
+                "{},{},{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.1},{:.2},{:.2},{:.2}\n",
+                config.black_bot,
+                config.black_limits,
+                config.white_bot,
+                config.white_limits,
+                n,
+                summary.black_wins as f64 / n as f64,
+                black_low,
+                black_high,
+                summary.white_wins as f64 / n as f64,
+                white_low,
+                white_high,
+                summary.draws as f64 / n as f64,
+                summary.unfinished as f64 / n as f64,
+                summary.elo_diff(),
+                mean(&summary.turns),
+                mean(&summary.black_nodes),
+                mean(&summary.white_nodes),
+            );
+        }
+        csv
+    }
+
+    /// Returns the formatted rows of all configurations
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.summaries
+            .iter()
+            .map(|(config, summary)| {
+                let mut row = vec![
+                    config.black_bot.clone(),
+                    config.black_limits.clone(),
+                    config.white_bot.clone(),
+                    config.white_limits.clone(),
+                ];
+                row.extend(summary.columns());
+                row
+            })
+            .collect()
+    }
+}
+
+/// returns the Wilson score interval of k successes in n trials
+fn wilson_interval(k: usize, n: usize, z: f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let n = n as f64;
+    let p = k as f64 / n;
+    let denominator = 1.0 + z * z / n;
+    let center = (p + z * z / (2.0 * n)) / denominator;
+    let margin = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+/// returns the mean of the data or 0 if there is no data
+fn mean(data: &[f64]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    data.iter().sum::<f64>() / data.len() as f64
+}
+
+impl std::fmt::Display for ParseResultsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ResultsErrorKind::Io(err) => write!(f, "file could not be read: {}", err),
+            ResultsErrorKind::FileName(name) => {
+                write!(f, "file name {} does not encode a bot configuration", name)
+            }
+            ResultsErrorKind::Line(line) => write!(f, "line {} could not be parsed", line),
+            ResultsErrorKind::NoGames => write!(f, "file contains no games"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Z: f64 = 1.96;
+
+    #[test]
+    fn file_name_encodes_the_bots_and_limits() {
+        let config =
+            MatchConfig::from_file_name("2024-07-28-12-18-32-AlphaBeta-200-MCTS-n500s7-10")
+                .unwrap();
+        assert_eq!(config.black_bot, "AlphaBeta");
+        assert_eq!(config.black_limits, "200");
+        assert_eq!(config.white_bot, "MCTS");
+        assert_eq!(config.white_limits, "n500s7");
+    }
+
+    #[test]
+    fn file_name_with_other_number_of_parts_is_rejected() {
+        assert!(MatchConfig::from_file_name("").is_none());
+        assert!(MatchConfig::from_file_name("results.csv").is_none());
+        assert!(MatchConfig::from_file_name("2024-07-28-12-18-32-AlphaBeta-200-MCTS-10").is_none());
+        assert!(
+            MatchConfig::from_file_name("2024-07-28-12-18-32-AlphaBeta-200-MCTS-400-10-old")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn wilson_interval_without_successes_starts_at_zero() {
+        let (low, high) = wilson_interval(0, 10, Z);
+        assert_eq!(low, 0.0);
+        assert!((high - Z * Z / (10.0 + Z * Z)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wilson_interval_with_only_successes_ends_at_one() {
+        let (low, high) = wilson_interval(10, 10, Z);
+        assert!((low - 10.0 / (10.0 + Z * Z)).abs() < 1e-12);
+        assert_eq!(high, 1.0);
+    }
+
+    #[test]
+    fn wilson_interval_without_trials_is_uninformative() {
+        assert_eq!(wilson_interval(0, 0, Z), (0.0, 1.0));
+    }
+
+    #[test]
+    fn file_without_games_is_rejected() {
+        let dir = std::env::temp_dir().join(format!("report_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2024-07-28-12-18-32-AlphaBeta-200-MCTS-400-0");
+        fs::write(
+            &path,
+            "Game_ID, Result, num_turns, num_avg_black_nodes, std_black_nodes, num_avg_white_nodes, std_white_nodes\n",
+        )
+        .unwrap();
+
+        let mut report = ResultsReport::new();
+        assert!(report.add_file(&path).is_err());
+        assert!(report.rows().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
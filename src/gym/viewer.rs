@@ -0,0 +1,243 @@
+use std::{fs, path::Path};
+
+use crate::{
+    agent::{limits::SearchLimits, Bot},
+    corpus::record::GameRecord,
+    eval::Eval,
+    game::{
+        board::{Board, GameState},
+        position::Position,
+    },
+    render::{html::record_to_html, svg::board_to_svg},
+};
+
+/// directory the exported images and pages are written to
+const EXPORT_DIR: &str = "./export";
+
+/// The evaluator shown next to every position of a replay
+pub enum ViewerEval {
+    Nothing,
+    Static(Box<dyn Eval>),
+    Search(Box<dyn Bot>, SearchLimits),
+}
+
+pub enum ViewerCommand {
+    Next,
+    Back,
+    Jump(usize),
+    Start,
+    End,
+    Svg,
+    Html,
+    Quit,
+}
+
+pub struct ReplayViewer {
+    record: GameRecord,
+    positions: Vec<(Board, Vec<Position>)>,
+    ply: usize,
+    eval: ViewerEval,
+}
+
+impl ViewerCommand {
+    /// Parse a viewer command from a string
+    pub fn from_str(str: &str) -> Option<ViewerCommand> {
+        let mut parts = str.split_whitespace();
+
+        match parts.next() {
+            None | Some("n") => Some(ViewerCommand::Next),
+            Some("b") => Some(ViewerCommand::Back),
+            Some("s") => Some(ViewerCommand::Start),
+            Some("e") => Some(ViewerCommand::End),
+            Some("w") => Some(ViewerCommand::Svg),
+            Some("h") => Some(ViewerCommand::Html),
+            Some("q") => Some(ViewerCommand::Quit),
+            Some("j") => parts
+                .next()
+                .and_then(|ply| ply.parse::<usize>().ok())
+                .map(ViewerCommand::Jump),
+            _ => None,
+        }
+    }
+}
+
+impl ReplayViewer {
+    /// Create a new viewer for the given game
+    pub fn new(record: GameRecord, eval: ViewerEval) -> ReplayViewer {
+        let positions = record.replay();
+        ReplayViewer {
+            record,
+            positions,
+            ply: 0,
+            eval,
+        }
+    }
+
+    /// Get the last ply of the game
+    pub fn last_ply(&self) -> usize {
+        self.positions.len() - 1
+    }
+
+    /// Steps one ply forward. Returns false at the end of the game.
+    pub fn forward(&mut self) -> bool {
+        self.jump_to(self.ply + 1)
+    }
+
+    /// Steps one ply back. Returns false at the start of the game.
+    pub fn back(&mut self) -> bool {
+        self.ply > 0 && self.jump_to(self.ply - 1)
+    }
+
+    /// Jumps to the given ply. Returns false if the ply does not exist.
+    pub fn jump_to(&mut self, ply: usize) -> bool {
+        if ply > self.last_ply() {
+            return false;
+        }
+        self.ply = ply;
+        true
+    }
+
+    /// Prints the current position together with the last move, its captures and the eval
+    pub fn show(&mut self) {
+        let (board, captured) = &self.positions[self.ply];
+
+        println!(
+            "Game {} of {} - ply {}/{}",
+            self.record.get_game_id(),
+            self.record.get_source(),
+            self.ply,
+            self.last_ply()
+        );
+        println!("{}", board);
+
+        if self.ply > 0 {
+            println!("Last move: {}", self.record.get_moves()[self.ply - 1]);
+            if captured.is_empty() {
+                println!("Captures: none");
+            } else {
+                let captured: Vec<String> = captured.iter().map(|pos| pos.to_string()).collect();
+                println!("Captures: {}", captured.join(", "));
+            }
+        }
+
+        match &mut self.eval {
+            ViewerEval::Nothing => {}
+            ViewerEval::Static(eval) => {
+                println!(
+                    "Eval (positive favours attacker): {:.3}",
+                    eval.get_eval(board)
+                )
+            }
+            ViewerEval::Search(bot, limits) => {
+                if !board.is_game_over() {
+                    match bot.get_next_move(board, limits) {
+                        Some(mov) => {
+                            println!("{} suggests: {}", bot.get_name(), mov);
+                            if let Some(info) = bot.get_search_info() {
+                                println!("{}", info);
+                            }
+                        }
+                        None => println!("{} found no move", bot.get_name()),
+                    }
+                }
+            }
+        }
+
+        // a loss on time is only known from the record
+        let state = if self.ply == self.last_ply() {
+            self.record.get_result()
+        } else {
+            board.who_won()
+        };
+        match state {
+            GameState::WinAttacker => println!("Attacker won!"),
+            GameState::WinDefender => println!("Defender won!"),
+            GameState::Draw => println!("It's a draw."),
+            GameState::Undecided => {}
+        }
+    }
+
+    /// Writes the current position as svg and returns the path of the file
+    pub fn export_svg(&self) -> Result<String, std::io::Error> {
+        let (board, captured) = &self.positions[self.ply];
+        let last_move = if self.ply == 0 {
+            None
+        } else {
+            self.record.get_moves().get(self.ply - 1)
+        };
+
+        let path = format!(
+            "{}/{}-{}-{}.svg",
+            EXPORT_DIR,
+            self.record.get_source(),
+            self.record.get_game_id(),
+            self.ply
+        );
+        fs::create_dir_all(Path::new(EXPORT_DIR))?;
+        fs::write(&path, board_to_svg(board, last_move, captured))?;
+        Ok(path)
+    }
+
+    /// Writes the game as html page and returns the path of the file
+    pub fn export_html(&self) -> Result<String, std::io::Error> {
+        let path = format!(
+            "{}/{}-{}.html",
+            EXPORT_DIR,
+            self.record.get_source(),
+            self.record.get_game_id()
+        );
+        fs::create_dir_all(Path::new(EXPORT_DIR))?;
+        fs::write(&path, record_to_html(&self.record))?;
+        Ok(path)
+    }
+
+    /// Lets the user step through the game
+    pub fn run(&mut self) {
+        println!("Commands: 'n' (or enter) next, 'b' back, 'j X' jump to ply X,");
+        println!("          's' start, 'e' end, 'w' save position as svg,");
+        println!("          'h' save game as html, 'q' quit.");
+        self.show();
+
+        loop {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_line(&mut input)
+                .expect("can not read user input");
+
+            let moved = match ViewerCommand::from_str(&input) {
+                Some(ViewerCommand::Next) => self.forward(),
+                Some(ViewerCommand::Back) => self.back(),
+                Some(ViewerCommand::Jump(ply)) => self.jump_to(ply),
+                Some(ViewerCommand::Start) => self.jump_to(0),
+                Some(ViewerCommand::End) => self.jump_to(self.last_ply()),
+                Some(ViewerCommand::Svg) => {
+                    match self.export_svg() {
+                        Ok(path) => println!("Position written to {}.", path),
+                        Err(err) => println!("Position could not be written: {}", err),
+                    }
+                    continue;
+                }
+                Some(ViewerCommand::Html) => {
+                    match self.export_html() {
+                        Ok(path) => println!("Game written to {}.", path),
+                        Err(err) => println!("Game could not be written: {}", err),
+                    }
+                    continue;
+                }
+                Some(ViewerCommand::Quit) => break,
+                None => {
+                    println!(
+                        "command does not exist, try 'n', 'b', 'j X', 's', 'e', 'w', 'h' or 'q'"
+                    );
+                    continue;
+                }
+            };
+
+            if moved {
+                self.show();
+            } else {
+                println!("No such ply, the game has {} plies.", self.last_ply());
+            }
+        }
+    }
+}
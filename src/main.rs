@@ -1,26 +1,45 @@
 mod agent;
+mod corpus;
 mod eval;
 mod game;
 mod gym;
+mod render;
 mod synthesis;
 mod utils;
 
 use core::time;
+use std::path::Path;
 use std::thread::{self};
 
-use agent::{alpha_beta::policy::AlphaBetaBot, mcts::policy::Mcts, random::policy::RandomBot};
+use agent::alpha_beta::policy::{AlphaBetaBot, AlphaBetaParams};
+use agent::book::{policy::BookBot, table::OpeningBook};
+use agent::limits::SearchLimits;
+use agent::mcts::policy::{Mcts, MctsParams, MoveSelection, ParallelMode};
+use agent::mcts::prior::{EvalPrior, HeuristicPrior};
+use agent::random::policy::RandomBot;
 use agent::{Bot, BotInit};
+use corpus::analysis::CorpusAnalysis;
+use corpus::index::{CorpusIndex, KingPattern, PositionHit};
+use corpus::record::{list_replay_files, load_replay_dir, load_replay_file};
+use eval::heuristic_rollout::{HeuristicRollout, HeuristicRolloutParam};
 use eval::human_score::{HumanScore, HumanScoreParam};
+use eval::neural_net::NeuralNet;
 use eval::random_rollout::RandomRollout;
+use eval::squashed::Squashed;
 use eval::EvalInit;
 use game::board::BOARDSIZE;
+use game::clock::{Clock, SideTime};
 use game::move_generation::MoveGen;
 use game::position::Position;
+use game::r#move::Move;
 use game::{
     board::{Board, GameState},
     piece::PieceColor,
 };
+use gym::benchmark::{benchmark_alpha_beta, benchmark_mcts};
 use gym::fight::Arena;
+use gym::report::ResultsReport;
+use gym::viewer::{ReplayViewer, ViewerEval};
 use synthesis::network::Network;
 use tch::Device;
 use utils::action::Action;
@@ -32,12 +51,18 @@ fn main() {
     println!("2) Human vs. Bot");
     println!("3) Training");
     println!("4) Watch random game with NN evaluations");
-    let mode = read_usize_in_range(1, 4);
+    println!("5) Watch a stored replay");
+    println!("6) Report over ./results");
+    println!("7) Search the replay corpus");
+    println!("8) Analyse the replay corpus");
+    println!("9) Benchmark MCTS");
+    println!("10) Benchmark alpha-beta");
+    let mode = read_usize_in_range(1, 10);
 
     if mode == 1 {
         println!("Playing Bot vs. Bot");
-        let (black_time_limit, mut black_bot) = choose_bot(PieceColor::Attacker);
-        let (white_time_limit, mut white_bot) = choose_bot(PieceColor::Defender);
+        let (black_limits, mut black_bot) = choose_bot(PieceColor::Attacker);
+        let (white_limits, mut white_bot) = choose_bot(PieceColor::Defender);
 
         let mut arena = Arena::new(&mut black_bot, &mut white_bot);
 
@@ -50,7 +75,7 @@ fn main() {
 
         println!("Playing games...");
         println!("Please see ./results for the outcomes.");
-        arena.play_games(games_to_play, black_time_limit, white_time_limit, verbose);
+        arena.play_games(games_to_play, &black_limits, &white_limits, verbose);
     } else if mode == 2 {
         println!("Playing Human vs. Bot");
         println!("Which side do you want to play? (0: black, 1: white)");
@@ -66,21 +91,40 @@ fn main() {
             panic!("You didn't choose a color");
         };
 
-        let (bot_time_limit, mut bot) = choose_bot(my_color.get_opposite());
+        let (bot_limits, mut bot) = choose_bot(my_color.get_opposite());
+        println!("Should the bot think while it is your turn? (0: no, 1: yes)");
+        let ponder = read_usize_in_range(0, 1) == 1;
 
-        game_loop(my_color, bot_time_limit, &mut bot);
+        game_loop(my_color, &bot_limits, &mut bot, ponder);
     } else if mode == 3 {
         simple_taining_loop();
     } else if mode == 4 {
         watch_game_with_eval();
+    } else if mode == 5 {
+        watch_replay();
+    } else if mode == 6 {
+        results_report();
+    } else if mode == 7 {
+        search_corpus();
+    } else if mode == 8 {
+        analyse_corpus();
+    } else if mode == 9 {
+        benchmark();
+    } else if mode == 10 {
+        benchmark_threads();
     } else {
         println!("You didn't choose a valid play mode.");
     }
 }
 
-/// choose bot
-fn choose_bot(color: PieceColor) -> (u128, Box<dyn Bot>) {
-    let eval = HumanScore::new(HumanScoreParam {
+/// human evaluation that is mapped to tanh(1) by the MCTS value function
+const VALUE_SCALE: f64 = 10.0;
+/// temperature of the softmax over the human evaluations of the moves
+const PRIOR_TEMPERATURE: f64 = 10.0;
+
+/// the human evaluation used by default
+fn default_human_score() -> HumanScore {
+    HumanScore::new(HumanScoreParam {
         w_ring_1: 0.0,
         w_ring_2: 0.0,
         w_ring_3: 1.0,
@@ -88,13 +132,23 @@ fn choose_bot(color: PieceColor) -> (u128, Box<dyn Bot>) {
         w_corner: 1.0,
         w_edge: -1.0,
         w_king_dst: -1.0,
-    });
+    })
+}
 
+/// choose bot
+fn choose_bot(color: PieceColor) -> (SearchLimits, Box<dyn Bot>) {
     let word_for_color = match color {
         PieceColor::Attacker => "Attacker",
         PieceColor::Defender => "Defender",
     };
     println!("Please choose the Bot playing {}:", word_for_color);
+    choose_bot_type(word_for_color)
+}
+
+/// choose the type and search limits of a bot
+fn choose_bot_type(word_for_color: &str) -> (SearchLimits, Box<dyn Bot>) {
+    let eval = default_human_score();
+
     println!("1) Random");
     println!("2) MCTS");
     println!("3) Alpha-Beta");
@@ -103,60 +157,283 @@ fn choose_bot(color: PieceColor) -> (u128, Box<dyn Bot>) {
     let bot: Box<dyn Bot> = if black_bot_choice == 1 {
         Box::new(RandomBot::new(2, RandomRollout::new(1)))
     } else if black_bot_choice == 2 {
-        Box::new(Mcts::new(1.4, RandomRollout::new(1)))
+        let mut params = MctsParams::new(1.4);
+        println!("Should the tree be kept between moves? (0: no, 1: yes)");
+        params.reuse_tree = read_usize_in_range(0, 1) == 1;
+        println!("Should proven wins and losses be propagated? (0: no, 1: yes)");
+        params.use_solver = read_usize_in_range(0, 1) == 1;
+        println!("Should RAVE be used? (0: no, 1: yes)");
+        if read_usize_in_range(0, 1) == 1 {
+            println!("After how many visits should RAVE and UCT be weighted equally?");
+            params.rave_equivalence = Some(read_usize_in_range(1, usize::MAX) as f64);
+        }
+        println!("Which selection should be used? (0: UCT, 1: PUCT with heuristic priors, 2: PUCT with priors from the static evaluation)");
+        match read_usize_in_range(0, 2) {
+            1 => params.prior = Some(Box::new(HeuristicPrior)),
+            2 => {
+                params.prior = Some(Box::new(EvalPrior::new(
+                    default_human_score(),
+                    PRIOR_TEMPERATURE,
+                )))
+            }
+            _ => {}
+        }
+        println!("Which move should be played after the search? (0: highest mean, 1: most visits, 2: highest lower confidence bound)");
+        params.move_selection = match read_usize_in_range(0, 2) {
+            0 => MoveSelection::Max,
+            1 => MoveSelection::Robust,
+            _ => MoveSelection::Secure,
+        };
+        println!("How should leaves be evaluated? (0: random rollouts, 1: static evaluation, 2: heuristic rollouts)");
+        let leaf_eval = read_usize_in_range(0, 2);
+        let heuristic_rollout = (leaf_eval == 2).then(choose_heuristic_rollout);
+        let max_threads: usize = thread::available_parallelism().unwrap().into();
+        println!(
+            "How many threads should the search use? (1 - {})",
+            max_threads
+        );
+        params.num_threads = read_usize_in_range(1, max_threads);
+        if params.num_threads > 1 {
+            println!("Should the threads share one tree? (0: one tree, 1: one tree per thread)");
+            if read_usize_in_range(0, 1) == 1 {
+                params.parallel_mode = ParallelMode::Root;
+            }
+        }
+        if let Some(rollout) = heuristic_rollout {
+            Box::new(Mcts::new(params, rollout))
+        } else if leaf_eval == 1 {
+            Box::new(Mcts::new(
+                params,
+                Squashed::new((default_human_score(), VALUE_SCALE)),
+            ))
+        } else {
+            Box::new(Mcts::new(params, RandomRollout::new(1)))
+        }
     } else {
         println!("Choose the max depth of the search");
-        let max_depth = read_usize_in_range(1, 5);
-        Box::new(AlphaBetaBot::new(max_depth, eval))
+        let max_depth = read_usize_in_range(1, 10);
+        let mut params = AlphaBetaParams::new(max_depth);
+        println!("How many MB should the transposition table use?");
+        params.tt_size_mb = read_usize_in_range(1, 65536);
+        println!("How many plies may the quiescence search add? (0: no quiescence search)");
+        params.quiescence_depth = read_usize_in_range(0, 32);
+        println!("Use principal variation search? (0: no, 1: yes)");
+        params.use_pvs = read_usize_in_range(0, 1) == 1;
+        println!("Use aspiration windows? (0: no, 1: yes)");
+        params.use_aspiration = read_usize_in_range(0, 1) == 1;
+        println!("Use null move pruning? (0: no, 1: yes)");
+        params.use_null_move = read_usize_in_range(0, 1) == 1;
+        println!("Use late move reductions? (0: no, 1: yes)");
+        params.use_lmr = read_usize_in_range(0, 1) == 1;
+        let max_threads: usize = thread::available_parallelism().unwrap().into();
+        println!(
+            "How many threads should the search use? (1 - {})",
+            max_threads
+        );
+        params.num_threads = read_usize_in_range(1, max_threads);
+        println!("How many plies of the search tree should be recorded for export? (0: none)");
+        params.trace_depth = match read_usize_in_range(0, max_depth) {
+            0 => None,
+            depth => Some(depth),
+        };
+        Box::new(AlphaBetaBot::new(params, eval))
     };
+    let limits = choose_search_limits(word_for_color);
+
+    println!("Should the bot play from an opening book? (0: no, 1: yes)");
+    if read_usize_in_range(0, 1) == 1 {
+        return (limits, Box::new(BookBot::new(load_opening_book(), bot, 2)));
+    }
+
+    (limits, bot)
+}
+
+/// choose how the search of a bot is limited for each move
+fn choose_search_limits(word_for_color: &str) -> SearchLimits {
+    println!(
+        "How should the search of {} be limited? (0: time, 1: nodes, 2: depth, 3: clock)",
+        word_for_color
+    );
+    match read_usize_in_range(0, 3) {
+        0 => {
+            println!(
+                "How much time in ms should {} have for each move?",
+                word_for_color
+            );
+            SearchLimits::movetime(read_usize_in_range(0, usize::MAX) as u128)
+        }
+        1 => {
+            println!(
+                "How many nodes may {} search for each move?",
+                word_for_color
+            );
+            let limits = SearchLimits::nodes(read_usize_in_range(1, usize::MAX));
+            limits.with_seed(choose_seed(word_for_color))
+        }
+        2 => {
+            println!("How deep may {} search for each move?", word_for_color);
+            let limits = SearchLimits::depth(read_usize_in_range(1, usize::MAX));
+            limits.with_seed(choose_seed(word_for_color))
+        }
+        _ => {
+            println!(
+                "How much time in ms should {} have for the whole game?",
+                word_for_color
+            );
+            let main_ms = read_usize_in_range(1, usize::MAX) as u128;
+            println!(
+                "How much time in ms should {} get after each move?",
+                word_for_color
+            );
+            let increment_ms = read_usize_in_range(0, usize::MAX) as u128;
+            SearchLimits::clock(SideTime::new(main_ms, increment_ms))
+        }
+    }
+}
+
+/// choose the seed of the random choices of a search, so a search with one thread
+/// that is limited by nodes or depth always plays the same moves
+fn choose_seed(word_for_color: &str) -> Option<u64> {
     println!(
-        "How much time in ms should {} have for each move?",
+        "Which seed should the search of {} use? (0: a different one every time)",
         word_for_color
     );
-    let time_limit: u128 = read_usize_in_range(0, usize::MAX)
-        .try_into()
-        .expect("Failed converting time limit.");
+    match read_usize_in_range(0, usize::MAX) {
+        0 => None,
+        seed => Some(seed as u64),
+    }
+}
+
+/// choose the randomness and the cutoff of heuristic rollouts,
+/// cut off rollouts are scored by the squashed human evaluation
+fn choose_heuristic_rollout() -> HeuristicRollout<Squashed<HumanScore>> {
+    println!("How many percent of the rollout moves should be random?");
+    let epsilon = read_usize_in_range(0, 100) as f64 / 100.0;
+    println!("After how many plies should rollouts be cut off? (0: play until the game is over)");
+    let max_depth = match read_usize_in_range(0, usize::MAX) {
+        0 => None,
+        depth => Some(depth),
+    };
 
-    (time_limit, bot)
+    HeuristicRollout::new(HeuristicRolloutParam {
+        epsilon,
+        max_depth,
+        cutoff_eval: Squashed::new((default_human_score(), VALUE_SCALE)),
+    })
 }
 
-/// Lets the player interact with a bot
-fn game_loop(player_color: PieceColor, bot_time_limit: u128, bot: &mut Box<dyn Bot>) {
+/// Loads the opening book from ./opening_book or builds it from ./replays
+fn load_opening_book() -> OpeningBook {
+    let book_path = Path::new("./opening_book");
+
+    match OpeningBook::load(book_path) {
+        Ok(book) => {
+            println!(
+                "Loaded opening book with {} positions.",
+                book.num_positions()
+            );
+            return book;
+        }
+        Err(err) => println!(
+            "Could not load ./opening_book ({}), building it from ./replays.",
+            err
+        ),
+    }
+
+    let book = OpeningBook::from_records(&load_replay_dir(Path::new("./replays")), 12);
+    println!(
+        "Built opening book with {} positions.",
+        book.num_positions()
+    );
+    if let Err(err) = book.save(book_path) {
+        println!("Could not save opening book: {}", err);
+    }
+    book
+}
+
+/// Lets the player interact with a bot.
+/// With pondering the bot keeps searching while the player thinks.
+fn game_loop(
+    player_color: PieceColor,
+    bot_limits: &SearchLimits,
+    bot: &mut Box<dyn Bot>,
+    ponder: bool,
+) {
     println!("Welcome to Hnefatafl! :D");
     println!("You can make a move by typing 'mm X1 Y1 X2 Y2'.");
     println!("You can also get a list of possible moves for a position by typing 'pm X Y'.");
     println!("The position 'A 3' must be given as '10 3'.");
+    println!("You can export the tree of the last bot search by typing 'export FILE [DEPTH] [MIN_VISITS]'.");
     let mut board = Board::new();
 
     let mut turn = PieceColor::Attacker;
+    // with a clock for the bot, the player gets the same time
+    let mut clock = Clock::new(bot_limits.clock, bot_limits.clock);
+    // the bot ponders while it is the turn of the player
+    let mut pondering = false;
+    let mut expected_reply: Option<Move> = None;
+
+    bot.set_info_callback(Box::new(|info| println!("Bot thinks: {}", info)));
 
+    clock.start();
     while !board.is_game_over() {
         println!("{}", board);
+        if let (Some(attacker), Some(defender)) = (
+            clock.get_time(&PieceColor::Attacker),
+            clock.get_time(&PieceColor::Defender),
+        ) {
+            println!(
+                "Time left: attacker {} ms, defender {} ms",
+                attacker.remaining_ms, defender.remaining_ms
+            );
+        }
 
         if turn == player_color {
             println!("please perform an action.");
-            let action = read_string();
+            let action = match pondering {
+                true => read_string_pondering(bot, &board),
+                false => read_string(),
+            };
             match action {
                 Some(Action::Quit) => break,
                 Some(Action::Nothing) => println!("No action performed"),
+                Some(Action::ExportTree(path, max_depth, min_visits)) => {
+                    export_search_trace(bot.as_ref(), &path, max_depth, min_visits)
+                }
                 Some(act) => {
                     let res = board.perform_action(&act, &player_color);
 
-                    if let Action::MakeMove(_) = act {
+                    if let Action::MakeMove(mov) = act {
                         match res {
                             Err(msg) => println!("{}", msg),
-                            Ok(()) => turn.flip(),
+                            Ok(()) => {
+                                if expected_reply.take() == Some(mov) {
+                                    println!("The bot expected this move.");
+                                }
+                                if !clock.stop(&turn) {
+                                    break;
+                                }
+                                clock.start();
+                                turn.flip()
+                            }
                         }
                     }
                 }
-                None => println!("action does not exist, try 'mm' or 'pm'"),
+                None => println!("action does not exist, try 'mm', 'pm' or 'export'"),
             }
         } else {
-            let mov = bot.get_next_move(&board, bot_time_limit);
+            let limits = bot_limits.with_clock(clock.get_time(&turn));
+            let mov = bot.get_next_move(&board, &limits);
+            if !clock.stop(&turn) {
+                break;
+            }
+            clock.start();
             match mov {
                 Some(m) => {
                     println!("Bot move: {}", m);
                     let _ = board.make_move_captured_positions(&m);
+                    pondering = ponder && !board.is_game_over();
+                    expected_reply = bot.get_expected_reply(&m);
                 }
                 None => println!("Bot failed to move"),
             };
@@ -165,11 +442,40 @@ fn game_loop(player_color: PieceColor, bot_time_limit: u128, bot: &mut Box<dyn B
         println!();
     }
 
-    match board.who_won() {
-        GameState::WinAttacker => println!("Attacker won!"),
-        GameState::WinDefender => println!("Defender won!"),
-        GameState::Draw => println!("It's a draw."),
-        GameState::Undecided => println!("Time ran out."),
+    match clock.get_flagged() {
+        Some(PieceColor::Attacker) => println!("Attacker ran out of time, defender won!"),
+        Some(PieceColor::Defender) => println!("Defender ran out of time, attacker won!"),
+        None => match board.who_won() {
+            GameState::WinAttacker => println!("Attacker won!"),
+            GameState::WinDefender => println!("Defender won!"),
+            GameState::Draw => println!("It's a draw."),
+            GameState::Undecided => println!("Time ran out."),
+        },
+    }
+}
+
+/// Reads an action of the player while the bot ponders on the board in a background thread.
+/// The pondering stops as soon as the action has been entered.
+fn read_string_pondering(bot: &mut Box<dyn Bot>, board: &Board) -> Option<Action> {
+    let limits = SearchLimits::infinite();
+    thread::scope(|scope| {
+        let pondering = scope.spawn(|| bot.ponder(board, &limits));
+        let action = read_string();
+        limits.stop.stop();
+        pondering.join().unwrap();
+        action
+    })
+}
+
+/// Writes the tree of the last search of the bot to `<path>.dot` and `<path>.json`
+fn export_search_trace(bot: &dyn Bot, path: &str, max_depth: usize, min_visits: u32) {
+    let Some(trace) = bot.get_search_trace(max_depth, min_visits) else {
+        println!("The bot has no search tree to export.");
+        return;
+    };
+    match trace.save(Path::new(path)) {
+        Ok(()) => println!("Saved the search tree to {0}.dot and {0}.json", path),
+        Err(err) => println!("Could not save the search tree: {}", err),
     }
 }
 
@@ -251,8 +557,12 @@ fn watch_game_with_eval() {
         //mcts.reset(&board);
 
         let mov = match turn {
-            PieceColor::Attacker => random.get_next_move(&board, 100).unwrap(),
-            PieceColor::Defender => random.get_next_move(&board, 1000).unwrap(),
+            PieceColor::Attacker => random
+                .get_next_move(&board, &SearchLimits::movetime(100))
+                .unwrap(),
+            PieceColor::Defender => random
+                .get_next_move(&board, &SearchLimits::movetime(1000))
+                .unwrap(),
         };
 
         println!("{}", mov);
@@ -272,6 +582,209 @@ fn watch_game_with_eval() {
     }
 }
 
+/// Lets the user step through a game of ./replays
+fn watch_replay() {
+    let files = list_replay_files(Path::new("./replays"));
+    if files.is_empty() {
+        println!("There are no replays in ./replays.");
+        return;
+    }
+
+    println!("Which replay file do you want to watch?");
+    for (idx, file) in files.iter().enumerate() {
+        println!("{}) {}", idx, file.file_name().unwrap().to_string_lossy());
+    }
+    let file = &files[read_usize_in_range(0, files.len() - 1)];
+
+    let mut games = match load_replay_file(file) {
+        Ok(games) if !games.is_empty() => games,
+        Ok(_) => {
+            println!("The replay file does not contain any games.");
+            return;
+        }
+        Err(err) => {
+            println!("The replay file could not be loaded: {}", err);
+            return;
+        }
+    };
+
+    println!("Which game do you want to watch? (0 - {})", games.len() - 1);
+    let record = games.swap_remove(read_usize_in_range(0, games.len() - 1));
+
+    println!("Which evaluation should be shown for every position?");
+    println!("0) None");
+    println!("1) Human score");
+    println!("2) Neural network");
+    println!("3) Search bot");
+    let eval = match read_usize_in_range(0, 3) {
+        1 => ViewerEval::Static(Box::new(default_human_score())),
+        2 => {
+            println!("Please enter the path of the network:");
+            let mut path = String::new();
+            std::io::stdin()
+                .read_line(&mut path)
+                .expect("can not read user input");
+            ViewerEval::Static(Box::new(NeuralNet::new(path.trim().to_string())))
+        }
+        3 => {
+            println!("Please choose the Bot analysing the game:");
+            let (limits, bot) = choose_bot_type("the analysis");
+            ViewerEval::Search(bot, limits)
+        }
+        _ => ViewerEval::Nothing,
+    };
+
+    ReplayViewer::new(record, eval).run();
+}
+
+/// Summarises all matches of ./results grouped by bot configuration
+fn results_report() {
+    let report = ResultsReport::from_dir(Path::new("./results"));
+
+    println!("In which format should the report be written? (0: Markdown, 1: CSV)");
+    let (content, file_name) = if read_usize_in_range(0, 1) == 0 {
+        (report.to_markdown(), "./results_report.md")
+    } else {
+        (report.to_csv(), "./results_report.csv")
+    };
+
+    println!("{}", content);
+    match std::fs::write(file_name, content) {
+        Ok(()) => println!("The report was written to {}.", file_name),
+        Err(err) => println!("The report could not be written: {}", err),
+    }
+}
+
+/// Measures the playout rate and the memory per node of MCTS
+fn benchmark() {
+    println!("How much time in ms should be spent on each position?");
+    let time_limit = read_usize_in_range(1, usize::MAX) as u128;
+
+    let mut params = MctsParams::new(1.4);
+    let max_threads: usize = thread::available_parallelism().unwrap().into();
+    println!(
+        "How many threads should the search use? (1 - {})",
+        max_threads
+    );
+    params.num_threads = read_usize_in_range(1, max_threads);
+
+    println!("Which evaluation should be used? (0: random rollouts, 1: static evaluation)");
+    let benchmark = if read_usize_in_range(0, 1) == 0 {
+        benchmark_mcts(params, RandomRollout::new(1), time_limit)
+    } else {
+        benchmark_mcts(params, default_human_score(), time_limit)
+    };
+    println!("{}", benchmark);
+}
+
+/// Measures how the node rate and the reached depth of alpha-beta scale with the threads
+fn benchmark_threads() {
+    println!("How much time in ms should be spent on each position?");
+    let time_limit = read_usize_in_range(1, usize::MAX) as u128;
+
+    let max_threads: usize = thread::available_parallelism().unwrap().into();
+    println!(
+        "Up to how many threads should be measured? (1 - {})",
+        max_threads
+    );
+    let threads = read_usize_in_range(1, max_threads);
+
+    for benchmark in benchmark_alpha_beta(threads, default_human_score, time_limit) {
+        println!("{}", benchmark);
+    }
+}
+
+/// Finds the games of ./replays that reached a position or pattern
+fn search_corpus() {
+    let index = CorpusIndex::from_records(load_replay_dir(Path::new("./replays")));
+    println!(
+        "Indexed {} unique games ({} duplicates dropped) with {} positions.",
+        index.num_games(),
+        index.num_duplicates(),
+        index.num_positions()
+    );
+
+    println!("What do you want to search for?");
+    println!("0) A position given by the moves leading to it");
+    println!("1) King on the edge");
+    println!("2) King with a free file to the edge");
+    println!("3) King on the edge with a free file to a corner");
+
+    let hits: Vec<&PositionHit>;
+    let pattern_hits: Vec<PositionHit>;
+
+    match read_usize_in_range(0, 3) {
+        0 => {
+            println!(
+                "Enter one move per line like '(7, A) -> (9, A)' and finish with an empty line."
+            );
+            let mut board = Board::new();
+            loop {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_line(&mut input)
+                    .expect("can not read user input");
+                if input.trim().is_empty() {
+                    break;
+                }
+                match Move::from_record_str(&input) {
+                    Ok(mov) if board.is_valid_move(&mov, &board.get_player()) => {
+                        board.make_move_captured_positions(&mov);
+                    }
+                    Ok(mov) => println!("Move {} is not legal here and was ignored.", mov),
+                    Err(err) => println!("Move could not be parsed: {}", err),
+                }
+            }
+            println!("{}", board);
+            hits = index.find_position(&board);
+        }
+        choice => {
+            let pattern = match choice {
+                1 => KingPattern::OnEdge,
+                2 => KingPattern::FreeFile,
+                _ => KingPattern::OnEdgeWithFreeFile,
+            };
+            pattern_hits = index.find_pattern(&pattern);
+            hits = pattern_hits.iter().collect();
+        }
+    }
+
+    println!("{} games reached the position.", hits.len());
+    println!("Outcomes: {}", index.summarize(hits.iter().copied()));
+    for hit in hits.iter().take(20) {
+        let game = index.get_game(hit.get_game_idx());
+        println!(
+            "{} game {} at ply {} (played {} times): {:?}",
+            game.get_record().get_source(),
+            game.get_record().get_game_id(),
+            hit.get_ply(),
+            game.get_occurrences(),
+            game.get_result()
+        );
+    }
+    if hits.len() > 20 {
+        println!("... and {} more.", hits.len() - 20);
+    }
+}
+
+/// Computes heatmaps and statistics over the games of ./replays
+fn analyse_corpus() {
+    let analysis = CorpusAnalysis::from_records(&load_replay_dir(Path::new("./replays")));
+
+    println!("How many plies should be grouped together?");
+    let bucket_size = read_usize_in_range(1, 1000);
+
+    println!("Where should the analysis be written? (0: terminal, 1: CSV files in ./analysis)");
+    if read_usize_in_range(0, 1) == 0 {
+        analysis.print(bucket_size);
+    } else {
+        match analysis.write_csv(Path::new("./analysis"), bucket_size) {
+            Ok(()) => println!("The analysis was written to ./analysis."),
+            Err(err) => println!("The analysis could not be written: {}", err),
+        }
+    }
+}
+
 /// Trains the network
 fn simple_taining_loop() {
     let mut net = Network::new();
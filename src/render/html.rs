@@ -0,0 +1,123 @@
+use crate::{corpus::record::GameRecord, game::board::GameState};
+
+use super::svg::{board_to_svg, svg_size};
+
+/// Exports a game as a self-contained html page.
+/// Every position is embedded as svg and can be stepped through with
+/// the forward and back buttons or the arrow keys.
+pub fn record_to_html(record: &GameRecord) -> String {
+    let title = escape_html(&format!(
+        "Game {} of {}",
+        record.get_game_id(),
+        record.get_source()
+    ));
+    let replay = record.replay();
+    let moves = record.get_moves();
+
+    let mut frames = String::new();
+    for (ply, (board, captured)) in replay.iter().enumerate() {
+        let last_move = if ply == 0 { None } else { moves.get(ply - 1) };
+        let caption = match last_move {
+            Some(mov) => format!("Ply {}: {}", ply, mov),
+            None => "Start position".to_string(),
+        };
+        frames += &format!(
+            "<div class=\"frame\" data-caption=\"{}\">\n{}</div>\n",
+            escape_html(&caption),
+            board_to_svg(board, last_move, captured)
+        );
+    }
+
+    let result = match record.get_result() {
+        GameState::WinAttacker => "Attacker won",
+        GameState::WinDefender => "Defender won",
+        GameState::Draw => "Draw",
+        GameState::Undecided => "Unfinished",
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 20px; }}
+.frame {{ display: none; }}
+.controls {{ margin: 10px 0; width: {width}px; text-align: center; }}
+button {{ font-size: 16px; padding: 4px 12px; }}
+</style>
+</head>
+<body>
+<h2>{title}</h2>
+<p>{result} after {plies} plies.</p>
+<div class="controls">
+<button id="first">&lt;&lt;</button>
+<button id="back">&lt;</button>
+<span id="caption"></span>
+<button id="forward">&gt;</button>
+<button id="last">&gt;&gt;</button>
+</div>
+{frames}<script>
+const frames = document.getElementsByClassName("frame");
+let ply = 0;
+function show(newPly) {{
+    ply = Math.max(0, Math.min(frames.length - 1, newPly));
+    for (let i = 0; i < frames.length; i++) {{
+        frames[i].style.display = i === ply ? "block" : "none";
+    }}
+    document.getElementById("caption").textContent = frames[ply].dataset.caption;
+}}
+document.getElementById("first").onclick = () => show(0);
+document.getElementById("back").onclick = () => show(ply - 1);
+document.getElementById("forward").onclick = () => show(ply + 1);
+document.getElementById("last").onclick = () => show(frames.length - 1);
+document.addEventListener("keydown", (event) => {{
+    if (event.key === "ArrowLeft") show(ply - 1);
+    if (event.key === "ArrowRight") show(ply + 1);
+}});
+show(0);
+</script>
+</body>
+</html>
+"#,
+        width = svg_size(),
+        plies = moves.len(),
+    )
+}
+
+/// escapes the characters that have a meaning in html text and attributes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(
+            escape_html(r#"a<b>&"c" -> (7, A)"#),
+            "a&lt;b&gt;&amp;&quot;c&quot; -&gt; (7, A)"
+        );
+    }
+
+    #[test]
+    fn file_name_is_escaped_in_the_page() {
+        let record = GameRecord::new("<script>alert(1)</script>".to_string(), 0, vec![]);
+        let html = record_to_html(&record);
+        assert!(!html.contains("<script>alert"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::game::{
+    board::{Board, BOARDSIZE},
+    piece::PieceColor,
+    position::Position,
+    r#move::Move,
+};
+
+/// width of a single square in pixels
+const SQUARE: usize = 40;
+/// space around the board used for the coordinates
+const MARGIN: usize = 24;
+
+/// Returns the size of the svg image in pixels
+pub fn svg_size() -> usize {
+    2 * MARGIN + BOARDSIZE * SQUARE
+}
+
+/// returns the pixel coordinates of the center of the position
+fn center(pos: &Position) -> (usize, usize) {
+    (
+        MARGIN + pos.get_y() * SQUARE + SQUARE / 2,
+        MARGIN + pos.get_x() * SQUARE + SQUARE / 2,
+    )
+}
+
+/// Renders the board as svg.
+/// The last move is drawn as an arrow and captured positions are crossed out.
+pub fn board_to_svg(board: &Board, last_move: Option<&Move>, captured: &[Position]) -> String {
+    let size = svg_size();
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n"
+    );
+
+    svg += "<defs><marker id=\"arrowhead\" markerWidth=\"6\" markerHeight=\"6\" refX=\"3\" refY=\"3\" orient=\"auto\">\
+            <path d=\"M0,0 L6,3 L0,6 Z\" fill=\"#1565c0\"/></marker></defs>\n";
+    svg += &format!("<rect width=\"{size}\" height=\"{size}\" fill=\"#f5e6c8\"/>\n");
+
+    // coordinates in the same format as the terminal board
+    for i in 0..BOARDSIZE {
+        let offset = MARGIN + i * SQUARE + SQUARE / 2;
+        svg += &format!(
+            "<text x=\"{offset}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+            MARGIN - 8,
+            (65 + i as u8) as char
+        );
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{:02}</text>\n",
+            MARGIN / 2,
+            offset + 4,
+            i
+        );
+    }
+
+    // squares with special colors for the throne and the corners
+    for num in 0..BOARDSIZE * BOARDSIZE {
+        let pos = Position::new_n(num);
+        let fill = if pos.is_corner() {
+            "#8d6e63"
+        } else if pos.is_throne() {
+            "#bcaaa4"
+        } else {
+            "#e8d3a9"
+        };
+        svg += &format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"{fill}\" stroke=\"#6d4c41\" stroke-width=\"1\"/>\n",
+            MARGIN + pos.get_y() * SQUARE,
+            MARGIN + pos.get_x() * SQUARE,
+        );
+    }
+
+    // pieces
+    let radius = SQUARE * 2 / 5;
+    for num in 0..BOARDSIZE * BOARDSIZE {
+        let pos = Position::new_n(num);
+        let (cx, cy) = center(&pos);
+
+        if board.has_color_piece(&pos, &PieceColor::Attacker) {
+            svg += &format!(
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"#212121\" stroke=\"#000\" stroke-width=\"2\"/>\n"
+            );
+        } else if board.get_king_pos().is_some_and(|king_pos| king_pos == pos) {
+            svg += &format!(
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"#fafafa\" stroke=\"#c62828\" stroke-width=\"3\"/>\n\
+                <path d=\"M{} {cy} H{} M{cx} {} V{}\" stroke=\"#c62828\" stroke-width=\"3\"/>\n",
+                cx - radius / 2,
+                cx + radius / 2,
+                cy - radius / 2,
+                cy + radius / 2,
+            );
+        } else if board.has_color_piece(&pos, &PieceColor::Defender) {
+            svg += &format!(
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"#fafafa\" stroke=\"#000\" stroke-width=\"2\"/>\n"
+            );
+        }
+    }
+
+    // captured positions
+    let half = SQUARE / 3;
+    for pos in captured {
+        let (cx, cy) = center(pos);
+        svg += &format!(
+            "<path d=\"M{} {} L{} {} M{} {} L{} {}\" stroke=\"#d32f2f\" stroke-width=\"4\"/>\n",
+            cx - half,
+            cy - half,
+            cx + half,
+            cy + half,
+            cx - half,
+            cy + half,
+            cx + half,
+            cy - half,
+        );
+    }
+
+    // last move
+    if let Some(mov) = last_move {
+        let (x1, y1) = center(mov.get_start_pos());
+        let (x2, y2) = center(mov.get_end_pos());
+        svg += &format!(
+            "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#1565c0\" stroke-width=\"4\" \
+            stroke-opacity=\"0.8\" marker-end=\"url(#arrowhead)\"/>\n"
+        );
+    }
+
+    svg += "</svg>\n";
+    svg
+}
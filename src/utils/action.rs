@@ -1,8 +1,15 @@
 use crate::game::{position::Position, r#move::Move};
 
+/// plies of the search tree exported if no depth is given
+const DEFAULT_EXPORT_DEPTH: usize = 3;
+/// visits a node needs to be exported if no minimum is given
+const DEFAULT_EXPORT_VISITS: u32 = 1;
+
 pub enum Action {
     PossibleMoves(Position),
     MakeMove(Move),
+    /// export the tree of the last bot search to a file, down to a depth and with a minimum of visits
+    ExportTree(String, usize, u32),
     Quit,
     Nothing,
 }
@@ -40,6 +47,18 @@ impl Action {
                     }
                 }
             }
+            Some("export") => {
+                let path = parts.next()?.to_string();
+                let max_depth = match parts.next() {
+                    Some(depth) => depth.parse().ok()?,
+                    None => DEFAULT_EXPORT_DEPTH,
+                };
+                let min_visits = match parts.next() {
+                    Some(visits) => visits.parse().ok()?,
+                    None => DEFAULT_EXPORT_VISITS,
+                };
+                Some(Action::ExportTree(path, max_depth, min_visits))
+            }
             Some("quit") => Some(Action::Quit),
             None => Some(Action::Nothing),
             _ => None,
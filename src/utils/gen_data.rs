@@ -3,7 +3,12 @@ use std::{thread, vec};
 use tch::{Device, Tensor};
 
 use crate::{
-    agent::{alpha_beta::policy::AlphaBetaBot, random::policy::RandomBot, Bot, BotInit},
+    agent::{
+        alpha_beta::policy::{AlphaBetaBot, AlphaBetaParams},
+        limits::SearchLimits,
+        random::policy::RandomBot,
+        Bot, BotInit,
+    },
     eval::{neural_net::NeuralNet, random_rollout::RandomRollout, EvalInit},
     game::{
         board::{Board, GameState},
@@ -36,10 +41,14 @@ impl Generator {
         let mut handles = vec![];
 
         for _ in 0..thread_count {
-            let _attacker_mcts =
-                Box::new(AlphaBetaBot::new(3, NeuralNet::new(attacker_nn.clone())));
-            let _defender_mcts =
-                Box::new(AlphaBetaBot::new(3, NeuralNet::new(defender_nn.clone())));
+            let _attacker_mcts = Box::new(AlphaBetaBot::new(
+                AlphaBetaParams::new(3),
+                NeuralNet::new(attacker_nn.clone()),
+            ));
+            let _defender_mcts = Box::new(AlphaBetaBot::new(
+                AlphaBetaParams::new(3),
+                NeuralNet::new(defender_nn.clone()),
+            ));
 
             let attacker_mcts = Box::new(RandomBot::new(1, RandomRollout::new(1)));
             let defender_mcts = Box::new(RandomBot::new(1, RandomRollout::new(1)));
@@ -103,8 +112,12 @@ fn rollout_with_observations(
 
         while !rollout_board.is_game_over() {
             let mov = match rollout_board.get_player() {
-                PieceColor::Attacker => mcts_attacker.get_next_move(&rollout_board, 100),
-                PieceColor::Defender => mcts_defender.get_next_move(&rollout_board, 100),
+                PieceColor::Attacker => {
+                    mcts_attacker.get_next_move(&rollout_board, &SearchLimits::movetime(100))
+                }
+                PieceColor::Defender => {
+                    mcts_defender.get_next_move(&rollout_board, &SearchLimits::movetime(100))
+                }
             }
             .unwrap();
 
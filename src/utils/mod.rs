@@ -1,2 +1,3 @@
 pub mod action;
 pub mod gen_data;
+pub mod rng;
@@ -0,0 +1,20 @@
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+thread_local! {
+    /// random number generator of the thread, seeded by the OS until a search sets a seed
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Seeds the random number generator of the current thread,
+/// so the random choices that follow are the same every time
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs the function with the random number generator of the current thread.
+/// The function must not use the generator itself again.
+pub fn with_rng<R>(f: impl FnOnce(&mut StdRng) -> R) -> R {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}